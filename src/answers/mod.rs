@@ -0,0 +1,318 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::error::Error;
+use crate::solution::{self, Answer, Solver};
+use crate::Data;
+
+// Known answers, keyed by day, data source and part. Stored on disk as a
+// small subset of TOML:
+//
+//     [day1.input]
+//     part1 = 69795
+//     part2 = 208437
+//
+//     [day5."./inputs/day5.txt"]
+//     part1 = "CMZ"
+#[derive(Debug, Default, PartialEq)]
+pub struct Answers {
+    entries: BTreeMap<(usize, String, usize), String>,
+}
+
+// How a result compares with the known answer
+#[derive(Debug, PartialEq)]
+pub enum Check {
+    Correct,
+    Wrong(String),
+    Unknown,
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Check::Correct => write!(f, "✓"),
+            Check::Wrong(want) => write!(f, "✗ expected {}", want),
+            Check::Unknown => write!(f, "(unknown)"),
+        }
+    }
+}
+
+// The name answers are stored under for a data source. Stdin and strings
+// can't be recognised again later, so they have no answers.
+pub fn data_key(input: &Data) -> Option<String> {
+    match input {
+        Data::Input => Some(String::from("input")),
+        Data::Test => Some(String::from("test")),
+        Data::Example(name) => Some(format!("example:{}", name)),
+        Data::Path(path) => Some(path.display().to_string()),
+        Data::Stdin | Data::Str(_) => None,
+    }
+}
+
+impl Answers {
+    // A missing file just means nothing is known yet
+    pub fn load(path: &Path) -> Result<Answers, Error> {
+        let file = path.display().to_string();
+        match fs::read_to_string(path) {
+            Ok(text) => Answers::parse(&text).map_err(|err| err.in_file(&file)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Answers::default()),
+            Err(source) => Err(Error::IO { path: file, source }),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Answers, Error> {
+        let mut answers = Answers::default();
+        let mut table: Option<(usize, String)> = None;
+        for (idx, line) in text.lines().enumerate() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            let res = if trimmed.starts_with('[') {
+                parse_header(line, trimmed).map(|header| table = Some(header))
+            } else {
+                answers.parse_entry(line, trimmed, table.as_ref())
+            };
+            res.map_err(|err| err.at_line(idx + 1))?;
+        }
+        Ok(answers)
+    }
+
+    fn parse_entry(&mut self, line: &str, trimmed: &str, table: Option<&(usize, String)>) -> Result<(), Error> {
+        let (key, value) = match trimmed.split_once('=') {
+            Some(split) => split,
+            None => return Err(Error::parse(line, trimmed, "expected 'part1 = ...' or a [dayN.data] header")),
+        };
+        let key = key.trim();
+        let part = match key {
+            "part1" => 1,
+            "part2" => 2,
+            _ => return Err(Error::parse(line, key, format!("unknown key '{}', expected part1 or part2", key))),
+        };
+        let (day, data) = match table {
+            Some(table) => table,
+            None => return Err(Error::parse(line, key, "answer given before any [dayN.data] header")),
+        };
+        let value = parse_value(line, value.trim())?;
+        if self.entries.insert((*day, data.clone(), part), value).is_some() {
+            return Err(Error::parse(line, key, format!("{} is given twice for day{}.{}", key, day, data)));
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, day: usize, data: &str, part: usize) -> Option<&str> {
+        self.entries.get(&(day, data.to_string(), part)).map(String::as_str)
+    }
+
+    pub fn insert(&mut self, day: usize, data: &str, part: usize, answer: &Answer) {
+        self.entries.insert((day, data.to_string(), part), answer.to_string());
+    }
+
+    // Inline examples carry their own answers, which are used when the file
+    // doesn't say, for test data as well when an example stands in for it
+    pub fn expected(&self, solver: &dyn Solver, input: &Data, part: usize) -> Option<String> {
+        let known = data_key(input).and_then(|key| self.get(solver.day(), &key, part).map(String::from));
+        known.or_else(|| {
+            let example = solution::example_for(solver.day(), solver.examples(), input)?;
+            let answer = if part == 1 { example.part1 } else { example.part2 };
+            answer.map(String::from)
+        })
+    }
+
+    pub fn check(&self, solver: &dyn Solver, input: &Data, part: usize, answer: &Answer) -> Check {
+        match self.expected(solver, input, part) {
+            None => Check::Unknown,
+            Some(want) if want == answer.to_string() => Check::Correct,
+            Some(want) => Check::Wrong(want),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        fs::write(path, self.to_string()).map_err(|source| Error::IO { path: path.display().to_string(), source })
+    }
+}
+
+impl fmt::Display for Answers {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "# Known answers, checked by `aoc2022 verify`")?;
+        let mut table: Option<(usize, &str)> = None;
+        for ((day, data, part), value) in &self.entries {
+            if table != Some((*day, data)) {
+                writeln!(f)?;
+                writeln!(f, "[day{}.{}]", day, quote_key(data))?;
+                table = Some((*day, data));
+            }
+            match value.parse::<i64>() {
+                Ok(_) => writeln!(f, "part{} = {}", part, value)?,
+                Err(_) => writeln!(f, "part{} = {}", part, quote(value))?,
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_header(line: &str, trimmed: &str) -> Result<(usize, String), Error> {
+    let inner = match trimmed.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+        Some(inner) => inner.trim(),
+        None => return Err(Error::parse(line, trimmed, "expected a header like [day1.input]")),
+    };
+    let (day, data) = match inner.split_once('.') {
+        Some(split) => split,
+        None => return Err(Error::parse(line, inner, "expected a header like [day1.input]")),
+    };
+    let number = match day.trim().strip_prefix("day") {
+        Some(number) => number,
+        None => return Err(Error::parse(line, day, format!("expected dayN, found '{}'", day))),
+    };
+    let day = number.parse::<usize>()
+        .map_err(|_| Error::parse(line, number, format!("'{}' is not a valid day", number)))?;
+    let data = data.trim();
+    let data = if data.starts_with('"') {
+        parse_string(line, data)?
+    } else if !data.is_empty() && data.chars().all(bare_char) {
+        data.to_string()
+    } else {
+        return Err(Error::parse(line, data, "data names other than letters, digits, '-' and '_' must be quoted"));
+    };
+    Ok((day, data))
+}
+
+// A quoted string or an integer, optionally followed by a comment
+fn parse_value(line: &str, value: &str) -> Result<String, Error> {
+    if value.starts_with('"') {
+        return parse_string(line, value);
+    }
+    let number = match value.split_once('#') {
+        Some((number, _)) => number.trim_end(),
+        None => value,
+    };
+    match number.parse::<i64>() {
+        Ok(n) => Ok(n.to_string()),
+        Err(_) => Err(Error::parse(line, number, "expected a number or a quoted string")),
+    }
+}
+
+// Basic TOML string with \" and \\ escapes; only a comment may follow it
+fn parse_string(line: &str, value: &str) -> Result<String, Error> {
+    let mut res = String::new();
+    let mut chars = value.char_indices().skip(1);
+    while let Some((idx, c)) = chars.next() {
+        match c {
+            '"' => {
+                let rest = value[idx + 1..].trim_start();
+                if !rest.is_empty() && !rest.starts_with('#') {
+                    return Err(Error::parse(line, rest, "unexpected text after string"));
+                }
+                return Ok(res);
+            }
+            '\\' => match chars.next() {
+                Some((_, c @ ('"' | '\\'))) => res.push(c),
+                _ => return Err(Error::parse(line, &value[idx..], "unsupported escape")),
+            },
+            c => res.push(c),
+        }
+    }
+    Err(Error::parse(line, value, "unterminated string"))
+}
+
+fn bare_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '-' || c == '_'
+}
+
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn quote_key(key: &str) -> String {
+    if !key.is_empty() && key.chars().all(bare_char) {
+        key.to_string()
+    } else {
+        quote(key)
+    }
+}
+
+#[cfg(test)]
+#[macro_use]
+mod tests {
+    use std::path::PathBuf;
+
+    use table_test::table_test;
+
+    use super::*;
+    use crate::day5::Day5;
+
+    const FILE: &str = "\
+# answers
+[day1.input]
+part1 = 69795   # checked
+part2 = 208437
+
+[day5.\"./inputs/day 5.txt\"]
+part1 = \"CMZ\"
+";
+
+    #[test]
+    fn parse() {
+        let answers = Answers::parse(FILE).unwrap();
+        assert_eq!(Some("69795"), answers.get(1, "input", 1));
+        assert_eq!(Some("208437"), answers.get(1, "input", 2));
+        assert_eq!(Some("CMZ"), answers.get(5, "./inputs/day 5.txt", 1));
+        assert_eq!(None, answers.get(5, "./inputs/day 5.txt", 2));
+        assert_eq!(answers, Answers::parse(&answers.to_string()).unwrap());
+    }
+
+    #[test]
+    fn parse_errors() {
+        let table = vec![
+            ("part1 = 5", (1, 1)),
+            ("[day1.input]\npart3 = 5", (2, 1)),
+            ("[day1.input]\npart1 = five", (2, 9)),
+            ("[day1.input]\npart1 = \"CMZ", (2, 9)),
+            ("[dayx.input]", (1, 5)),
+            ("[day1]", (1, 2)),
+            ("[day1.a/b]", (1, 7)),
+            ("[day1.input]\npart1 = 1\npart1 = 2", (3, 1)),
+        ];
+
+        for (validator, input, expected) in table_test!(table) {
+            let actual = match Answers::parse(input) {
+                Err(Error::Parse(err)) => (err.line.unwrap_or(0), err.column),
+                _ => (0, 0),
+            };
+
+            validator
+                .given(input)
+                .when("Answers::parse")
+                .then(&format!("it should fail at {:?}", expected))
+                .assert_eq(expected, actual);
+        }
+    }
+
+    #[test]
+    fn check() {
+        let answers = Answers::parse(FILE).unwrap();
+        let path = Data::Path(PathBuf::from("./inputs/day 5.txt"));
+        let cmz = Answer::from(String::from("CMZ"));
+        assert_eq!(Check::Correct, answers.check(&Day5, &path, 1, &cmz));
+        assert_eq!(Check::Unknown, answers.check(&Day5, &path, 2, &cmz));
+        assert_eq!(Check::Unknown, answers.check(&Day5, &Data::Stdin, 1, &cmz));
+
+        // Falls back to the inline example's answers
+        let example = Data::Example(String::from("puzzle"));
+        assert_eq!(Check::Correct, answers.check(&Day5, &example, 1, &cmz));
+        assert_eq!(Check::Wrong(String::from("MCD")), answers.check(&Day5, &example, 2, &cmz));
+
+        // There's no local test.txt, so the test data is the first example
+        assert_eq!(Check::Correct, answers.check(&Day5, &Data::Test, 1, &cmz));
+        assert_eq!(Some(String::from("MCD")), answers.expected(&Day5, &Data::Test, 2));
+    }
+
+    #[test]
+    fn load_missing() {
+        let answers = Answers::load(Path::new("./no/such/answers.toml")).unwrap();
+        assert_eq!(Answers::default(), answers);
+    }
+}
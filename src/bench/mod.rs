@@ -0,0 +1,222 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::time::{Duration, Instant};
+
+use crate::error::Error;
+use crate::solution::{SolveResult, Solver};
+use crate::Data;
+
+// How long to keep sampling each day for
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Budget {
+    Runs(usize),
+    Time(Duration),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchConfig {
+    pub warmup: usize,
+    pub budget: Budget,
+}
+
+impl Default for BenchConfig {
+    fn default() -> BenchConfig {
+        BenchConfig {
+            warmup: 3,
+            budget: Budget::Runs(100),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stats {
+    pub runs: usize,
+    pub min: Duration,
+    pub median: Duration,
+    pub mean: Duration,
+    pub p95: Duration,
+    pub stddev: Duration,
+}
+
+impl Stats {
+    pub fn from_samples(samples: &[Duration]) -> Option<Stats> {
+        if samples.is_empty() {
+            return None;
+        }
+        let mut sorted = samples.to_vec();
+        sorted.sort();
+        let runs = sorted.len();
+        let median = if runs.is_multiple_of(2) {
+            (sorted[runs/2 - 1] + sorted[runs/2]) / 2
+        } else {
+            sorted[runs/2]
+        };
+        // Nearest-rank percentile
+        let p95 = sorted[((runs as f64 * 0.95).ceil() as usize).max(1) - 1];
+        let mean = sorted.iter().map(Duration::as_secs_f64).sum::<f64>() / runs as f64;
+        let variance = if runs > 1 {
+            sorted.iter().map(|d| (d.as_secs_f64() - mean).powi(2)).sum::<f64>() / (runs - 1) as f64
+        } else {
+            0.0
+        };
+        Some(Stats {
+            runs,
+            min: sorted[0],
+            median,
+            mean: Duration::from_secs_f64(mean),
+            p95,
+            stddev: Duration::from_secs_f64(variance.sqrt()),
+        })
+    }
+}
+
+// Statistics for one phase ("read", "parse", "part1", ...) of one day
+#[derive(Debug, Clone, PartialEq)]
+pub struct Measurement {
+    pub day: usize,
+    pub phase: String,
+    pub stats: Stats,
+}
+
+pub fn bench(solver: &dyn Solver, input: &Data, parts: &[usize], config: &BenchConfig) -> SolveResult<Vec<Measurement>> {
+    let mut phases: Vec<(String, Vec<Duration>)> = vec![
+        (String::from("read"), Vec::new()),
+        (String::from("parse"), Vec::new()),
+    ];
+    for part in parts {
+        phases.push((format!("part{}", part), Vec::new()));
+    }
+
+    let start = Instant::now();
+    let mut runs = 0;
+    loop {
+        let done = match config.budget {
+            Budget::Runs(n) => runs >= config.warmup + n,
+            // Always take at least one measured sample
+            Budget::Time(budget) => runs > config.warmup && start.elapsed() >= budget,
+        };
+        if done {
+            break;
+        }
+        let run = solver.run(input, parts)?;
+        runs += 1;
+        if runs <= config.warmup {
+            continue;
+        }
+        phases[0].1.push(run.read_time);
+        phases[1].1.push(run.parse_time);
+        for (idx, part) in run.parts.into_iter().enumerate() {
+            part.answer?;
+            phases[idx + 2].1.push(part.time);
+        }
+    }
+
+    Ok(phases
+        .into_iter()
+        .filter_map(|(phase, samples)| {
+            Stats::from_samples(&samples).map(|stats| Measurement {
+                day: solver.day(),
+                phase,
+                stats,
+            })
+        })
+        .collect())
+}
+
+pub fn table(measurements: &[Measurement]) -> String {
+    fn dur(d: Duration) -> String {
+        format!("{:>10}", format!("{:.2?}", d))
+    }
+    let mut res = format!(
+        "{:>3}  {:<6} {:>6} {:>10} {:>10} {:>10} {:>10} {:>10}\n",
+        "Day", "Phase", "Runs", "Min", "Median", "Mean", "p95", "Std dev"
+    );
+    for m in measurements {
+        let s = &m.stats;
+        writeln!(
+            res,
+            "{:>3}  {:<6} {:>6} {} {} {} {} {}",
+            m.day, m.phase, s.runs, dur(s.min), dur(s.median), dur(s.mean), dur(s.p95), dur(s.stddev)
+        ).unwrap();
+    }
+    res
+}
+
+// Durations are written as whole nanoseconds
+pub fn json(measurements: &[Measurement]) -> String {
+    let mut res = String::from("{\n  \"results\": [");
+    for (idx, m) in measurements.iter().enumerate() {
+        let s = &m.stats;
+        if idx > 0 {
+            res.push(',');
+        }
+        write!(
+            res,
+            "\n    {{\"day\": {}, \"phase\": \"{}\", \"runs\": {}, \"min_ns\": {}, \"median_ns\": {}, \"mean_ns\": {}, \"p95_ns\": {}, \"stddev_ns\": {}}}",
+            m.day, m.phase, s.runs,
+            s.min.as_nanos(), s.median.as_nanos(), s.mean.as_nanos(), s.p95.as_nanos(), s.stddev.as_nanos()
+        ).unwrap();
+    }
+    res.push_str("\n  ]\n}\n");
+    res
+}
+
+pub fn write_json(path: &str, measurements: &[Measurement]) -> Result<(), Error> {
+    fs::write(path, json(measurements)).map_err(|source| Error::IO { path: path.to_string(), source })
+}
+
+#[cfg(test)]
+#[macro_use]
+mod tests {
+    use table_test::table_test;
+
+    use super::*;
+    use crate::day6::Day6;
+
+    fn ms(samples: &[u64]) -> Vec<Duration> {
+        samples.iter().map(|n| Duration::from_millis(*n)).collect()
+    }
+
+    #[test]
+    fn stats() {
+        let table = vec![
+            (vec![5], (5, 5, 5, 5)),
+            (vec![6, 1, 2, 4], (1, 3, 3, 6)),
+            (vec![9, 1, 5], (1, 5, 5, 9)),
+            ((1..=99).collect(), (1, 50, 50, 95)),
+        ];
+
+        for (validator, input, (min, median, mean, p95)) in table_test!(table) {
+            let actual = Stats::from_samples(&ms(&input)).unwrap();
+            let ms = |d: Duration| (d.as_secs_f64() * 1000.0).round() as u64;
+
+            validator
+                .given(&format!("{:?}", input))
+                .when("Stats::from_samples")
+                .then(&format!("min {}, median {}, mean {}, p95 {}", min, median, mean, p95))
+                .assert_eq(min, ms(actual.min))
+                .assert_eq(median, ms(actual.median))
+                .assert_eq(mean, ms(actual.mean))
+                .assert_eq(p95, ms(actual.p95));
+        }
+    }
+
+    #[test]
+    fn stddev() {
+        let stats = Stats::from_samples(&ms(&[2, 4, 4, 4, 5, 5, 7, 9])).unwrap();
+        assert_eq!(2138, stats.stddev.as_micros());
+        assert!(Stats::from_samples(&[]).is_none());
+    }
+
+    #[test]
+    fn bench_runs() {
+        let config = BenchConfig {
+            warmup: 1,
+            budget: Budget::Runs(5),
+        };
+        let res = bench(&Day6, &Data::Example(String::from("puzzle")), &[1, 2], &config).unwrap();
+        let phases: Vec<(&str, usize)> = res.iter().map(|m| (m.phase.as_str(), m.stats.runs)).collect();
+        assert_eq!(vec![("read", 5), ("parse", 5), ("part1", 5), ("part2", 5)], phases);
+        assert!(json(&res).contains("\"phase\": \"part2\", \"runs\": 5"));
+    }
+}
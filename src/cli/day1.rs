@@ -0,0 +1,222 @@
+use crate::day1::{self, rebalance, stats, subsets, Day1, Elves, TopK};
+use crate::error::Error;
+use crate::solution::{self, Solution};
+use crate::Data;
+
+use super::{parse_count, parse_data, Args, CliError};
+
+pub const USAGE: &str = "\
+Usage: aoc2022 day1 top [--k K] [--data DATA]
+       aoc2022 day1 stats [--bins N] [--json] [--data DATA]
+       aoc2022 day1 rebalance [--exact | --heuristic] [--data DATA]
+       aoc2022 day1 groups --target CALORIES [--max-elves K] [--count N] [--data DATA]
+
+Tools:
+  top       List the K heaviest elves and their combined calories
+  stats     Summarise the calorie inventory, with a histogram of load per elf
+  rebalance Move items between elves so the heaviest pack is as light as
+            possible, and show the new packs
+  groups    Find groups of elves whose calories add up to a target, or
+            come as close to it as possible
+
+Options:
+      --k K         Number of elves (default: 3)
+      --bins N      Number of histogram bins (default: 10)
+      --json        Print the stats as JSON
+      --exact       Search for the best possible plan even with more than 40
+                    items, which can be slow
+      --heuristic   Only use the greedy plan and its improvements
+      --target CALORIES
+                    Combined calories to look for
+      --max-elves K Largest group to consider (default: any size)
+      --count N     Number of groups to list (default: 5)
+      --data DATA   Puzzle data to use, as for run (default: input)";
+
+#[derive(Debug, PartialEq)]
+pub enum Tool {
+    Top(usize),
+    Stats { bins: usize, json: bool },
+    Rebalance(rebalance::Mode),
+    Groups { target: usize, max_elves: Option<usize>, count: usize },
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Day1Args {
+    pub tool: Tool,
+    pub data: Data<'static>,
+}
+
+pub fn parse_args(args: &mut Args) -> Result<Day1Args, CliError> {
+    let tool = match args.subcommand().as_deref() {
+        Some("top") => {
+            let k = match args.value(&["--k"])? {
+                Some(k) => parse_count("--k", &k)?,
+                None => 3,
+            };
+            Tool::Top(k)
+        }
+        Some("stats") => {
+            let bins = match args.value(&["--bins"])? {
+                Some(bins) => parse_count("--bins", &bins)?,
+                None => 10,
+            };
+            Tool::Stats { bins, json: args.flag(&["--json"]) }
+        }
+        Some("rebalance") => {
+            let mode = match (args.flag(&["--exact"]), args.flag(&["--heuristic"])) {
+                (true, true) => return Err(CliError(String::from("--exact and --heuristic can't be used together"))),
+                (true, false) => rebalance::Mode::Exact,
+                (false, true) => rebalance::Mode::Heuristic,
+                (false, false) => rebalance::Mode::Auto,
+            };
+            Tool::Rebalance(mode)
+        }
+        Some("groups") => {
+            let target = match args.value(&["--target"])? {
+                Some(target) => parse_count("--target", &target)?,
+                None => return Err(CliError(String::from("groups needs --target CALORIES"))),
+            };
+            let max_elves = match args.value(&["--max-elves"])? {
+                Some(k) => Some(parse_count("--max-elves", &k)?),
+                None => None,
+            };
+            let count = match args.value(&["--count"])? {
+                Some(count) => parse_count("--count", &count)?,
+                None => 5,
+            };
+            Tool::Groups { target, max_elves, count }
+        }
+        Some(other) => return Err(CliError(format!("unknown day1 tool '{}' (see `aoc2022 day1 --help`)", other))),
+        None => return Err(CliError(String::from("day1 needs a tool (see `aoc2022 day1 --help`)"))),
+    };
+    let data = match args.value(&["--data"])? {
+        Some(data) => parse_data(&data)?,
+        None => Data::Input,
+    };
+    Ok(Day1Args { tool, data })
+}
+
+pub fn run(args: Day1Args) -> i32 {
+    match args.tool {
+        Tool::Top(k) => {
+            // Only the top K are kept, so any size of inventory can be ranked
+            let res = solution::open(Day1::DAY, Day1::EXAMPLES, &args.data).and_then(|(reader, file)| {
+                let mut top = TopK::new(k);
+                let mut count = 0;
+                for elf in Elves::new(reader, &file) {
+                    count += 1;
+                    top.insert(count, elf.map_err(|err| err.in_file(&file))?.cals);
+                }
+                Ok::<_, Error>((top, count))
+            });
+            let (top, count) = match res {
+                Ok(res) => res,
+                Err(err) => {
+                    eprintln!("error: day 1: {}", err);
+                    return 1;
+                }
+            };
+            println!("Top {} of {} elves:", top.elves().len(), count);
+            for (elf, cals) in top.elves() {
+                println!("  elf {}: {}", elf, cals);
+            }
+            println!("Total: {}", top.sum());
+        }
+        Tool::Stats { bins, json } => {
+            let res = solution::open(Day1::DAY, Day1::EXAMPLES, &args.data)
+                .and_then(|(reader, file)| day1::inventory(reader, &file).map_err(|err| err.in_file(&file)));
+            let elves = match res {
+                Ok(elves) => elves,
+                Err(err) => {
+                    eprintln!("error: day 1: {}", err);
+                    return 1;
+                }
+            };
+            match stats::stats(&elves, bins) {
+                Some(stats) if json => print!("{}", stats.json()),
+                Some(stats) => print!("{}", stats.text()),
+                None => println!("No elves"),
+            }
+        }
+        Tool::Rebalance(mode) => {
+            let res = solution::open(Day1::DAY, Day1::EXAMPLES, &args.data)
+                .and_then(|(reader, file)| day1::packs(reader, &file).map_err(|err| err.in_file(&file)))
+                .and_then(|packs| rebalance::rebalance(&packs, mode));
+            match res {
+                Ok(plan) => print!("{}", plan.text()),
+                Err(err) => {
+                    eprintln!("error: day 1: {}", err);
+                    return 1;
+                }
+            }
+        }
+        Tool::Groups { target, max_elves, count } => {
+            let res = solution::open(Day1::DAY, Day1::EXAMPLES, &args.data)
+                .and_then(|(reader, file)| {
+                    Elves::new(reader, &file)
+                        .map(|elf| elf.map(|elf| elf.cals).map_err(|err| err.in_file(&file)))
+                        .collect::<Result<Vec<isize>, Error>>()
+                })
+                .and_then(|totals| Ok((subsets::closest(&totals, target as i128, max_elves, count)?, totals)));
+            match res {
+                Ok((found, totals)) => print!("{}", found.text(&totals)),
+                Err(err) => {
+                    eprintln!("error: day 1: {}", err);
+                    return 1;
+                }
+            }
+        }
+    }
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::{parse_args, Command};
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn parse_top() {
+        let res = parse_args(args("day1 top --k 5 --data test")).unwrap();
+        let want = Command::Day1(Day1Args {
+            tool: Tool::Top(5),
+            data: Data::Test,
+        });
+        assert_eq!(want, res);
+
+        let res = parse_args(args("day1 top")).unwrap();
+        assert_eq!(Command::Day1(Day1Args { tool: Tool::Top(3), data: Data::Input }), res);
+
+        let res = parse_args(args("day1 stats --json --bins 4")).unwrap();
+        let want = Command::Day1(Day1Args {
+            tool: Tool::Stats { bins: 4, json: true },
+            data: Data::Input,
+        });
+        assert_eq!(want, res);
+
+        let res = parse_args(args("day1 rebalance --exact --data test")).unwrap();
+        let want = Command::Day1(Day1Args {
+            tool: Tool::Rebalance(rebalance::Mode::Exact),
+            data: Data::Test,
+        });
+        assert_eq!(want, res);
+
+        let res = parse_args(args("day1 groups --target 45000 --max-elves 3")).unwrap();
+        let want = Command::Day1(Day1Args {
+            tool: Tool::Groups { target: 45000, max_elves: Some(3), count: 5 },
+            data: Data::Input,
+        });
+        assert_eq!(want, res);
+
+        assert!(parse_args(args("day1")).is_err());
+        assert!(parse_args(args("day1 groups --count 2")).is_err());
+        assert!(parse_args(args("day1 rebalance --exact --heuristic")).is_err());
+        assert!(parse_args(args("day1 top --json")).is_err());
+        assert!(parse_args(args("day1 bottom")).is_err());
+        assert!(parse_args(args("day1 top --k x")).is_err());
+    }
+}
@@ -0,0 +1,337 @@
+use crate::day2::{self, analysis, breakdown, interpret, rules::Rules, Day2};
+use crate::day2::tournament::{self, Guide, Strategy, STRATEGIES};
+use crate::error::Error;
+use crate::solution::{self, Solution};
+use crate::Data;
+
+use super::{parse_count, parse_data, Args, CliError};
+
+pub const USAGE: &str = "\
+Usage: aoc2022 day2 score [--rules RULES] [--strict] [--data DATA]
+       aoc2022 day2 breakdown [--part PART] [--format FORMAT] [--rules RULES]
+                              [--strict] [--data DATA]
+       aoc2022 day2 interpret [--rules RULES] [--data DATA]
+       aoc2022 day2 analyse [--opponent MIX] [--rules RULES] [--data DATA]
+       aoc2022 day2 rules [--rules RULES]
+       aoc2022 day2 tournament [--bots BOTS] [--rounds N] [--seed SEED]
+                               [--rules RULES] [--data DATA]
+
+Tools:
+  score     Score a strategy guide under any rules, for both parts
+  breakdown Show every round of a part: the hands, outcome, scores and
+            running total, then how often each outcome and shape came up
+  interpret Rank every reading of the guide's second column, as each way of
+            assigning its codes to shapes or as the outcome needed, by score
+  analyse   Work out exactly what each shape scores on average against an
+            opponent's mix of shapes, the best response, and the equilibrium:
+            the mix nobody can outscore on average
+  rules     Show the shapes, their codes and scores, and who beats whom
+  tournament
+            Play bots against each other, every pair for N rounds, and show
+            the league table. The same seed always gives the same table.
+
+Bots:
+  guide     Plays your column of the guide, as shapes, over and over
+  elf       Plays the opponent's column of the guide
+  random    Plays any shape
+  frequency Beats the shape its opponent has played most
+  markov    Beats the shape its opponent most often plays after their last
+
+Options:
+      --rules RULES Built in rules (rps or rpsls) or a rules file (default: rps)
+      --strict      Fail on codes the rules don't define, instead of falling
+                    back to the last shape (or the first, and a loss, in part 2)
+      --data DATA   Puzzle data to use, as for run (default: input)
+      --part PART   Part to break down, 1 or 2 (default: 1)
+      --format FORMAT
+                    text, csv or json (default: text)
+      --opponent MIX
+                    uniform, guide (the mix in the guide's opponent column) or
+                    weights by shape, like rock=2,paper=1/2 (default: uniform)
+      --bots BOTS   Comma separated bots to enter, repeats allowed
+                    (default: guide,elf,random,frequency,markov)
+      --rounds N    Rounds per match (default: 1000)
+      --seed SEED   Seed for the bots' random choices (default: 2022)
+
+A rules file has one statement per line, and # starts a comment:
+  shape rock 1            A shape and its score
+  outcome win 6           Score for a loss, draw or win (default: 0, 3, 6)
+  rock beats scissors     Every pair of shapes needs a winner
+  opponent rock A R       Codes for a shape in the opponent's column
+  you rock X              Codes for a shape in your column
+  need win Z              Codes for the outcome you need, in part 2
+Without codes the opponent plays A, B, C, ... and you play ..., X, Y, Z, in
+the order the shapes are declared, and X, Y, Z ask for a loss, draw or win.";
+
+#[derive(Debug, PartialEq)]
+pub enum Tool {
+    Score,
+    Breakdown { part: usize, format: Format },
+    Interpret,
+    Analyse { opponent: String },
+    Rules,
+    Tournament { bots: Vec<Strategy>, rounds: usize, seed: u64 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Text,
+    Csv,
+    Json,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Day2Args {
+    pub tool: Tool,
+    pub rules: String,
+    pub strict: bool,
+    pub data: Data<'static>,
+}
+
+pub fn parse_args(args: &mut Args) -> Result<Day2Args, CliError> {
+    let tool = match args.subcommand().as_deref() {
+        Some("score") => Tool::Score,
+        Some("breakdown") => {
+            let part = match args.value(&["--part"])?.as_deref() {
+                Some("1") | None => 1,
+                Some("2") => 2,
+                Some(other) => return Err(CliError(format!("--part expects 1 or 2, not '{}'", other))),
+            };
+            let format = match args.value(&["--format"])?.as_deref() {
+                Some("text") | None => Format::Text,
+                Some("csv") => Format::Csv,
+                Some("json") => Format::Json,
+                Some(other) => return Err(CliError(format!("--format expects text, csv or json, not '{}'", other))),
+            };
+            Tool::Breakdown { part, format }
+        }
+        Some("interpret") => Tool::Interpret,
+        Some("analyse") => Tool::Analyse { opponent: args.value(&["--opponent"])?.unwrap_or_else(|| String::from("uniform")) },
+        Some("rules") => Tool::Rules,
+        Some("tournament") => {
+            let bots = match args.value(&["--bots"])? {
+                Some(bots) => parse_bots(&bots)?,
+                None => STRATEGIES.iter().map(|&(_, strategy)| strategy).collect(),
+            };
+            let rounds = match args.value(&["--rounds"])? {
+                Some(rounds) => parse_count("--rounds", &rounds)?,
+                None => 1000,
+            };
+            if rounds == 0 {
+                return Err(CliError(String::from("--rounds must be at least 1")));
+            }
+            let seed = match args.value(&["--seed"])? {
+                Some(seed) => seed.parse::<u64>().map_err(|_| CliError(format!("--seed expects a whole number, not '{}'", seed)))?,
+                None => 2022,
+            };
+            Tool::Tournament { bots, rounds, seed }
+        }
+        Some(other) => return Err(CliError(format!("unknown day2 tool '{}' (see `aoc2022 day2 --help`)", other))),
+        None => return Err(CliError(String::from("day2 needs a tool (see `aoc2022 day2 --help`)"))),
+    };
+    let rules = args.value(&["--rules"])?.unwrap_or_else(|| String::from("rps"));
+    let (strict, data) = match tool {
+        Tool::Score | Tool::Breakdown { .. } | Tool::Interpret | Tool::Analyse { .. } | Tool::Tournament { .. } => {
+            let data = match args.value(&["--data"])? {
+                Some(data) => parse_data(&data)?,
+                None => Data::Input,
+            };
+            let strict = matches!(tool, Tool::Score | Tool::Breakdown { .. }) && args.flag(&["--strict"]);
+            (strict, data)
+        }
+        Tool::Rules => (false, Data::Input),
+    };
+    Ok(Day2Args { tool, rules, strict, data })
+}
+
+fn parse_bots(spec: &str) -> Result<Vec<Strategy>, CliError> {
+    let bots = spec
+        .split(',')
+        .map(|name| Strategy::parse(name).ok_or_else(|| {
+            let names: Vec<&str> = STRATEGIES.iter().map(|&(name, _)| name).collect();
+            CliError(format!("unknown bot '{}' (expected {})", name, names.join(", ")))
+        }))
+        .collect::<Result<Vec<_>, _>>()?;
+    if bots.len() < 2 {
+        return Err(CliError(String::from("--bots needs at least two bots")));
+    }
+    Ok(bots)
+}
+
+pub fn run(args: Day2Args) -> i32 {
+    let rules = match Rules::load(&args.rules) {
+        Ok(rules) => rules,
+        Err(err) => {
+            eprintln!("error: day 2: {}", err);
+            return 1;
+        }
+    };
+    match args.tool {
+        Tool::Score => {
+            let res = solution::read(Day2::DAY, Day2::EXAMPLES, &args.data).and_then(|(text, file)| {
+                let guide = Day2.parse(&text).map_err(|err| err.in_file(&file))?;
+                let score = |part: fn(&Rules, bool, &[day2::Entry]) -> Result<isize, Error>| {
+                    part(&rules, args.strict, &guide).map_err(|err| err.in_file(&file))
+                };
+                Ok::<_, Error>((score(day2::part1)?, score(day2::part2)?))
+            });
+            match res {
+                Ok((part1, part2)) => {
+                    println!("Part 1: {}", part1);
+                    println!("Part 2: {}", part2);
+                }
+                Err(err) => {
+                    eprintln!("error: day 2: {}", err);
+                    return 1;
+                }
+            }
+        }
+        Tool::Breakdown { part, format } => {
+            let res = solution::read(Day2::DAY, Day2::EXAMPLES, &args.data).and_then(|(text, file)| {
+                let guide = Day2.parse(&text).map_err(|err| err.in_file(&file))?;
+                breakdown::breakdown(&rules, args.strict, part, &guide).map_err(|err| err.in_file(&file))
+            });
+            match res {
+                Ok(rounds) => match format {
+                    Format::Text => print!("{}", rounds.text(&rules)),
+                    Format::Csv => print!("{}", rounds.csv(&rules)),
+                    Format::Json => print!("{}", rounds.json(&rules)),
+                },
+                Err(err) => {
+                    eprintln!("error: day 2: {}", err);
+                    return 1;
+                }
+            }
+        }
+        Tool::Interpret => {
+            let res = solution::read(Day2::DAY, Day2::EXAMPLES, &args.data).and_then(|(text, file)| {
+                let guide = Day2.parse(&text).map_err(|err| err.in_file(&file))?;
+                interpret::interpret(&rules, &guide).map_err(|err| err.in_file(&file))
+            });
+            match res {
+                Ok(readings) => print!("{}", readings.text(&rules)),
+                Err(err) => {
+                    eprintln!("error: day 2: {}", err);
+                    return 1;
+                }
+            }
+        }
+        Tool::Analyse { opponent } => {
+            let mix = match opponent.as_str() {
+                "uniform" => Ok(analysis::uniform(&rules)),
+                "guide" => solution::read(Day2::DAY, Day2::EXAMPLES, &args.data).and_then(|(text, file)| {
+                    let guide = Day2.parse(&text).map_err(|err| err.in_file(&file))?;
+                    analysis::guide_mix(&rules, &guide).map_err(|err| err.in_file(&file))
+                }),
+                spec => analysis::parse_mix(&rules, spec).map_err(|err| err.in_file("--opponent").at_line(1)),
+            };
+            match mix.and_then(|mix| analysis::analyse(&rules, mix)) {
+                Ok(res) => print!("{}", res.text(&rules)),
+                Err(err) => {
+                    eprintln!("error: day 2: {}", err);
+                    return 1;
+                }
+            }
+        }
+        Tool::Rules => print!("{}", rules.describe()),
+        Tool::Tournament { bots, rounds, seed } => {
+            // Only the guide and elf bots need the data
+            let res = if bots.iter().any(|bot| bot.uses_guide()) {
+                solution::read(Day2::DAY, Day2::EXAMPLES, &args.data).and_then(|(text, file)| {
+                    let guide = Day2.parse(&text).map_err(|err| err.in_file(&file))?;
+                    Guide::new(&rules, &guide).map_err(|err| err.in_file(&file))
+                })
+            } else {
+                Ok(Guide::default())
+            };
+            match res.and_then(|guide| tournament::tournament(&rules, &guide, &bots, rounds, seed)) {
+                Ok(league) => print!("{}", league.text()),
+                Err(err) => {
+                    eprintln!("error: day 2: {}", err);
+                    return 1;
+                }
+            }
+        }
+    }
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::{parse_args, Command};
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn parse_score() {
+        let res = parse_args(args("day2 score --rules rpsls --strict --data test")).unwrap();
+        let want = Command::Day2(Day2Args {
+            tool: Tool::Score,
+            rules: String::from("rpsls"),
+            strict: true,
+            data: Data::Test,
+        });
+        assert_eq!(want, res);
+
+        let res = parse_args(args("day2 rules")).unwrap();
+        assert_eq!(Command::Day2(Day2Args { tool: Tool::Rules, rules: String::from("rps"), strict: false, data: Data::Input }), res);
+
+        assert!(parse_args(args("day2")).is_err());
+        assert!(parse_args(args("day2 rules --data test")).is_err());
+        assert!(parse_args(args("day2 rules --strict")).is_err());
+        assert!(parse_args(args("day2 interpret --strict")).is_err());
+        assert!(parse_args(args("day2 play")).is_err());
+    }
+
+    #[test]
+    fn parse_breakdown() {
+        let res = parse_args(args("day2 breakdown --part 2 --format=csv --strict")).unwrap();
+        let want = Command::Day2(Day2Args {
+            tool: Tool::Breakdown { part: 2, format: Format::Csv },
+            rules: String::from("rps"),
+            strict: true,
+            data: Data::Input,
+        });
+        assert_eq!(want, res);
+
+        match parse_args(args("day2 breakdown --data test")).unwrap() {
+            Command::Day2(Day2Args { tool, data, .. }) => assert_eq!((Tool::Breakdown { part: 1, format: Format::Text }, Data::Test), (tool, data)),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(parse_args(args("day2 breakdown --part 3")).is_err());
+        assert!(parse_args(args("day2 breakdown --format xml")).is_err());
+
+        let res = parse_args(args("day2 analyse --opponent rock=1,paper=2")).unwrap();
+        assert_eq!(Command::Day2(Day2Args {
+            tool: Tool::Analyse { opponent: String::from("rock=1,paper=2") },
+            rules: String::from("rps"),
+            strict: false,
+            data: Data::Input,
+        }), res);
+        assert!(parse_args(args("day2 analyse --strict")).is_err());
+    }
+
+    #[test]
+    fn parse_tournament() {
+        let res = parse_args(args("day2 tournament --bots random,markov,random --rounds 50 --seed 7 --rules rpsls")).unwrap();
+        let want = Command::Day2(Day2Args {
+            tool: Tool::Tournament { bots: vec![Strategy::Random, Strategy::Markov, Strategy::Random], rounds: 50, seed: 7 },
+            rules: String::from("rpsls"),
+            strict: false,
+            data: Data::Input,
+        });
+        assert_eq!(want, res);
+
+        match parse_args(args("day2 tournament")).unwrap() {
+            Command::Day2(Day2Args { tool: Tool::Tournament { bots, rounds: 1000, seed: 2022 }, .. }) => assert_eq!(5, bots.len()),
+            other => panic!("unexpected {:?}", other),
+        }
+        for line in ["day2 tournament --bots random", "day2 tournament --bots random,cheat", "day2 tournament --rounds 0",
+                     "day2 tournament --seed -1", "day2 tournament --strict"] {
+            assert!(parse_args(args(line)).is_err(), "'{}' should not parse", line);
+        }
+    }
+}
@@ -0,0 +1,202 @@
+use crate::day3::items::Alphabet;
+use crate::day3::{self, shared, Day3, Leftover, Rucksack};
+use crate::error::Error;
+use crate::solution::{self, Solution};
+use crate::Data;
+
+use super::{parse_count, parse_data, Args, CliError};
+
+pub const USAGE: &str = "\
+Usage: aoc2022 day3 priorities [--alphabet SPEC] [--data DATA]
+       aoc2022 day3 badges [--group N] [--leftover POLICY] [--list] [--alphabet SPEC] [--data DATA]
+       aoc2022 day3 shared [--group N] [--leftover POLICY] [--top K] [--alphabet SPEC] [--data DATA]
+
+Tools:
+  priorities
+            Solve both parts with the items and priorities of an alphabet
+  badges    Find the badge of every group of N rucksacks, in order, and add
+            up their priorities
+  shared    List every item each rucksack has in both compartments and each
+            group has in every rucksack, with how many of it each holds, then
+            the items most often misplaced
+
+Options:
+      --alphabet SPEC
+                    The items and their priorities: letters for the puzzle's
+                    a-z then A-Z, or an ordering file (default: letters)
+      --group N     Rucksacks per group (default: 3)
+      --leftover POLICY
+                    What to do with rucksacks that don't fill a last group:
+                    error, ignore them, or partial to badge the smaller group
+                    they make (default: error)
+      --list        List each group's lines and badge
+      --top K       Number of misplaced items to summarise (default: 10)
+      --data DATA   Puzzle data to use, as for run (default: input)
+
+An ordering file lists every item in priority order, lowest first. Any
+character but whitespace is an item, so they can be spread over lines or
+spaced out; lines starting with # are comments. Each item may appear only
+once, and a rucksack holding anything else is an error.";
+
+#[derive(Debug, PartialEq)]
+pub enum Tool {
+    Priorities,
+    Badges { size: usize, leftover: Leftover, list: bool },
+    Shared { size: usize, leftover: Leftover, top: usize },
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Day3Args {
+    pub tool: Tool,
+    pub alphabet: String,
+    pub data: Data<'static>,
+}
+
+pub fn parse_args(args: &mut Args) -> Result<Day3Args, CliError> {
+    let tool = match args.subcommand().as_deref() {
+        Some("priorities") => Tool::Priorities,
+        Some("badges") => {
+            let (size, leftover) = parse_groups(args)?;
+            Tool::Badges { size, leftover, list: args.flag(&["--list"]) }
+        }
+        Some("shared") => {
+            let (size, leftover) = parse_groups(args)?;
+            let top = match args.value(&["--top"])? {
+                Some(top) => parse_count("--top", &top)?,
+                None => 10,
+            };
+            Tool::Shared { size, leftover, top }
+        }
+        Some(other) => return Err(CliError(format!("unknown day3 tool '{}' (see `aoc2022 day3 --help`)", other))),
+        None => return Err(CliError(String::from("day3 needs a tool (see `aoc2022 day3 --help`)"))),
+    };
+    let alphabet = args.value(&["--alphabet"])?.unwrap_or_else(|| String::from("letters"));
+    let data = match args.value(&["--data"])? {
+        Some(data) => parse_data(&data)?,
+        None => Data::Input,
+    };
+    Ok(Day3Args { tool, alphabet, data })
+}
+
+fn parse_groups(args: &mut Args) -> Result<(usize, Leftover), CliError> {
+    let size = match args.value(&["--group"])? {
+        Some(size) => parse_count("--group", &size)?,
+        None => 3,
+    };
+    if size == 0 {
+        return Err(CliError(String::from("--group must be at least 1")));
+    }
+    let leftover = match args.value(&["--leftover"])?.as_deref() {
+        Some("error") | None => Leftover::Error,
+        Some("ignore") => Leftover::Ignore,
+        Some("partial") => Leftover::Partial,
+        Some(other) => return Err(CliError(format!("--leftover expects error, ignore or partial, not '{}'", other))),
+    };
+    Ok((size, leftover))
+}
+
+// The alphabet and the rucksacks read with it
+fn read(args: &Day3Args) -> Result<(Alphabet, Vec<Rucksack>, String), Error> {
+    let alphabet = Alphabet::load(&args.alphabet)?;
+    let (text, file) = solution::read(Day3::DAY, Day3::EXAMPLES, &args.data)?;
+    let rucksacks = day3::parse_rucksacks(&alphabet, &text).map_err(|err| err.in_file(&file))?;
+    Ok((alphabet, rucksacks, file))
+}
+
+pub fn run(args: Day3Args) -> i32 {
+    match args.tool {
+        Tool::Priorities => {
+            let res = read(&args).and_then(|(alphabet, rucksacks, file)| {
+                let part1 = day3::part1(&alphabet, &rucksacks).map_err(|err| err.in_file(&file))?;
+                let part2 = day3::part2(&alphabet, &rucksacks).map_err(|err| err.in_file(&file))?;
+                Ok::<_, Error>((alphabet.size(), part1, part2))
+            });
+            match res {
+                Ok((size, part1, part2)) => {
+                    println!("{} items, priorities 1 to {}", size, size);
+                    println!("Part 1: {}", part1);
+                    println!("Part 2: {}", part2);
+                }
+                Err(err) => {
+                    eprintln!("error: day 3: {}", err);
+                    return 1;
+                }
+            }
+        }
+        Tool::Badges { size, leftover, list } => {
+            let res = read(&args).and_then(|(alphabet, rucksacks, file)| {
+                let badges = day3::badges(&alphabet, &rucksacks, size, leftover).map_err(|err| err.in_file(&file))?;
+                Ok::<_, Error>((alphabet, badges, rucksacks.len()))
+            });
+            let (alphabet, badges, rucksacks) = match res {
+                Ok(res) => res,
+                Err(err) => {
+                    eprintln!("error: day 3: {}", err);
+                    return 1;
+                }
+            };
+            if list {
+                for badge in &badges {
+                    let lines = day3::lines(badge.first_line, badge.rucksacks);
+                    println!("{}: {} (priority {})", lines, alphabet.item(badge.priority), badge.priority);
+                }
+            }
+            let grouped: usize = badges.iter().map(|badge| badge.rucksacks).sum();
+            let total: usize = badges.iter().map(|badge| badge.priority).sum();
+            println!("{} groups of up to {}, badge priorities add up to {}", badges.len(), size, total);
+            if grouped < rucksacks {
+                println!("Left out the last {} rucksacks", rucksacks - grouped);
+            }
+        }
+        Tool::Shared { size, leftover, top } => {
+            let res = read(&args).and_then(|(alphabet, rucksacks, file)| {
+                shared::report(&alphabet, &rucksacks, size, leftover).map_err(|err| err.in_file(&file))
+            });
+            match res {
+                Ok(report) => print!("{}", report.text(top)),
+                Err(err) => {
+                    eprintln!("error: day 3: {}", err);
+                    return 1;
+                }
+            }
+        }
+    }
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::{parse_args, Command};
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn parse_badges() {
+        let res = parse_args(args("day3 badges --group 4 --leftover partial --list --data test")).unwrap();
+        let want = Command::Day3(Day3Args {
+            tool: Tool::Badges { size: 4, leftover: Leftover::Partial, list: true },
+            alphabet: String::from("letters"),
+            data: Data::Test,
+        });
+        assert_eq!(want, res);
+
+        let letters = || String::from("letters");
+        let res = parse_args(args("day3 badges")).unwrap();
+        let tool = Tool::Badges { size: 3, leftover: Leftover::Error, list: false };
+        assert_eq!(Command::Day3(Day3Args { tool, alphabet: letters(), data: Data::Input }), res);
+
+        let res = parse_args(args("day3 shared --group 2 --top 3")).unwrap();
+        let tool = Tool::Shared { size: 2, leftover: Leftover::Error, top: 3 };
+        assert_eq!(Command::Day3(Day3Args { tool, alphabet: letters(), data: Data::Input }), res);
+
+        let res = parse_args(args("day3 priorities --alphabet emoji.txt")).unwrap();
+        assert_eq!(Command::Day3(Day3Args { tool: Tool::Priorities, alphabet: String::from("emoji.txt"), data: Data::Input }), res);
+
+        for line in ["day3", "day3 badges --group 0", "day3 badges --leftover drop", "day3 shared --list", "day3 priorities --alphabet", "day3 sort"] {
+            assert!(parse_args(args(line)).is_err(), "'{}' should not parse", line);
+        }
+    }
+}
@@ -0,0 +1,830 @@
+use std::env;
+use std::fmt;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::answers::{data_key, Answers, Check};
+use crate::bench::{self, BenchConfig, Budget};
+use crate::error::Error;
+use crate::log::{self, Filter, Level};
+use crate::solution::{self, registry};
+use crate::util::{get_filename, read_to_string};
+use crate::Data;
+
+mod day1;
+mod day2;
+mod day3;
+
+const USAGE: &str = "\
+Usage: aoc2022 [run] [--day DAYS] [--part PART] [--data DATA] [--answers FILE] [--record]
+       aoc2022 verify [--day DAYS] [--part PART] [--data DATA] [--answers FILE]
+       aoc2022 bench [--day DAYS] [--part PART] [--data DATA] [--runs N | --time TIME]
+                     [--warmup N] [--json FILE]
+       aoc2022 examples [--day DAYS]
+       aoc2022 day1 TOOL [OPTIONS]
+       aoc2022 day2 TOOL [OPTIONS]
+       aoc2022 day3 TOOL [OPTIONS]
+       aoc2022 list
+
+Commands:
+  run       Run the selected days (default)
+  verify    Check the selected days against their known answers
+  bench     Time repeated runs of the selected days and report statistics
+  examples  Check the selected days against their inline examples
+  list      List the available days and their examples
+  day1      Extra tools for day 1 (see `aoc2022 day1 --help`)
+  day2      Extra tools for day 2 (see `aoc2022 day2 --help`)
+  day3      Extra tools for day 3 (see `aoc2022 day3 --help`)
+  help      Show this message
+
+Options:
+  -d, --day DAYS    Day, range or list of days to run: 5, 1-4, 1,3,6-7 or all (default: all)
+  -p, --part PART   Part to run: 1, 2 or all (default: all)
+      --data DATA   Puzzle data to use: input, test, example:NAME, a file path,
+                    or - for stdin (default: input)
+      --answers FILE  Known answers to check results against (default: answers.toml)
+      --record      Add answers that aren't known yet to the answers file
+
+Bench options:
+      --runs N      Measured runs per day (default: 100)
+      --time TIME   Keep running each day for this long instead, e.g. 500ms or 2s
+      --warmup N    Unmeasured runs before timing starts (default: 3)
+      --json FILE   Where to write the results as JSON (default: bench.json)
+
+Logging (to stderr, for any command):
+  -v, -vv, -vvv     Log info, debug or trace messages from every day
+      --log SPEC    Log levels per module, e.g. trace, day5=debug or info,day7=trace
+                    (default: the AOC_LOG environment variable)
+      --log-file FILE  Write log messages to FILE instead of stderr";
+
+#[derive(Debug, PartialEq)]
+pub struct CliError(String);
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Run(RunArgs),
+    Bench(BenchArgs),
+    Verify(RunArgs),
+    Day1(day1::Day1Args),
+    Day2(day2::Day2Args),
+    Day3(day3::Day3Args),
+    Examples(Vec<usize>),
+    List,
+    Help,
+    HelpFor(&'static str),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct RunArgs {
+    pub days: Vec<usize>,
+    pub parts: Vec<usize>,
+    pub data: Data<'static>,
+    pub answers: PathBuf,
+    pub record: bool,
+}
+
+impl Default for RunArgs {
+    fn default() -> RunArgs {
+        RunArgs {
+            days: all_days(),
+            parts: vec![1, 2],
+            data: Data::Input,
+            answers: PathBuf::from("answers.toml"),
+            record: false,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct BenchArgs {
+    pub run: RunArgs,
+    pub config: BenchConfig,
+    pub json: String,
+}
+
+impl Default for BenchArgs {
+    fn default() -> BenchArgs {
+        BenchArgs {
+            run: RunArgs::default(),
+            config: BenchConfig::default(),
+            json: String::from("bench.json"),
+        }
+    }
+}
+
+// Minimal argument scanner: options are pulled out by name, and anything
+// left over once a command has taken what it needs is reported as an error.
+pub struct Args {
+    args: Vec<String>,
+}
+
+impl Args {
+    pub fn new<I: IntoIterator<Item = String>>(args: I) -> Args {
+        Args { args: args.into_iter().collect() }
+    }
+
+    // The first argument, if it isn't an option
+    pub fn subcommand(&mut self) -> Option<String> {
+        match self.args.first() {
+            Some(arg) if !arg.starts_with('-') => Some(self.args.remove(0)),
+            _ => None,
+        }
+    }
+
+    // How many times any of `names` appears
+    pub fn count(&mut self, names: &[&str]) -> usize {
+        let before = self.args.len();
+        self.args.retain(|arg| !names.contains(&arg.as_str()));
+        before - self.args.len()
+    }
+
+    pub fn flag(&mut self, names: &[&str]) -> bool {
+        let before = self.args.len();
+        self.args.retain(|arg| !names.contains(&arg.as_str()));
+        self.args.len() != before
+    }
+
+    // Accepts both `--name value` and `--name=value`
+    pub fn value(&mut self, names: &[&str]) -> Result<Option<String>, CliError> {
+        for idx in 0..self.args.len() {
+            let arg = &self.args[idx];
+            if names.contains(&arg.as_str()) {
+                if idx + 1 >= self.args.len() {
+                    return Err(CliError(format!("{} needs a value", arg)));
+                }
+                let value = self.args.remove(idx + 1);
+                self.args.remove(idx);
+                return Ok(Some(value));
+            }
+            if let Some((name, value)) = arg.split_once('=') {
+                if names.contains(&name) {
+                    let value = value.to_string();
+                    self.args.remove(idx);
+                    return Ok(Some(value));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn finish(self) -> Result<(), CliError> {
+        match self.args.first() {
+            None => Ok(()),
+            Some(arg) => Err(CliError(format!("unexpected argument '{}'", arg))),
+        }
+    }
+}
+
+fn all_days() -> Vec<usize> {
+    registry().iter().map(|solver| solver.day()).collect()
+}
+
+// Parses "5", "1-4", "1,3,5-7" or "all" into a sorted list of days
+pub fn parse_days(spec: &str) -> Result<Vec<usize>, CliError> {
+    if spec == "all" {
+        return Ok(all_days());
+    }
+    let mut days = Vec::new();
+    for item in spec.split(',') {
+        let (lower, upper) = match item.split_once('-') {
+            Some((lower, upper)) => (parse_day(lower)?, parse_day(upper)?),
+            None => {
+                let day = parse_day(item)?;
+                (day, day)
+            }
+        };
+        if lower > upper {
+            return Err(CliError(format!("invalid day range '{}'", item)));
+        }
+        for day in lower..=upper {
+            if !days.contains(&day) {
+                days.push(day);
+            }
+        }
+    }
+    days.sort();
+    Ok(days)
+}
+
+fn parse_day(day: &str) -> Result<usize, CliError> {
+    match day.trim().parse::<usize>() {
+        Ok(day) if solution::get(day).is_some() => Ok(day),
+        _ => Err(CliError(format!("'{}' is not an available day (see `aoc2022 list`)", day))),
+    }
+}
+
+pub fn parse_parts(spec: &str) -> Result<Vec<usize>, CliError> {
+    match spec {
+        "1" => Ok(vec![1]),
+        "2" => Ok(vec![2]),
+        "all" | "both" => Ok(vec![1, 2]),
+        _ => Err(CliError(format!("'{}' is not a part (expected 1, 2 or all)", spec))),
+    }
+}
+
+// A path that happens to be called `input` or `test` can be given as ./input
+pub fn parse_data(spec: &str) -> Result<Data<'static>, CliError> {
+    match spec {
+        "input" => Ok(Data::Input),
+        "test" => Ok(Data::Test),
+        "-" => Ok(Data::Stdin),
+        "" => Err(CliError(String::from("--data needs a value"))),
+        _ if spec.starts_with("example:") => Ok(Data::Example(spec["example:".len()..].to_string())),
+        path => Ok(Data::Path(PathBuf::from(path))),
+    }
+}
+
+// Parses "2s", "1.5s" or "500ms"
+pub fn parse_duration(spec: &str) -> Result<Duration, CliError> {
+    let (number, scale) = match spec.strip_suffix("ms") {
+        Some(number) => (number, 0.001),
+        None => (spec.strip_suffix('s').unwrap_or(spec), 1.0),
+    };
+    match number.parse::<f64>() {
+        Ok(n) if n.is_finite() && n > 0.0 => Ok(Duration::from_secs_f64(n * scale)),
+        _ => Err(CliError(format!("'{}' is not a duration (expected e.g. 500ms or 2s)", spec))),
+    }
+}
+
+fn parse_count(name: &str, spec: &str) -> Result<usize, CliError> {
+    spec.parse::<usize>()
+        .map_err(|_| CliError(format!("{} expects a whole number, not '{}'", name, spec)))
+}
+
+#[derive(Debug, PartialEq)]
+pub struct LogArgs {
+    pub filter: Filter,
+    pub file: Option<PathBuf>,
+}
+
+// Logging options can go anywhere on the command line. `--log` replaces the
+// AOC_LOG variable, and -v flags raise the default level on top of either.
+pub fn parse_log_args(args: &mut Args, env_spec: Option<String>) -> Result<LogArgs, CliError> {
+    let verbosity = args.count(&["-v"]) + 2 * args.count(&["-vv"]) + 3 * args.count(&["-vvv"]);
+    let spec = match args.value(&["--log"])? {
+        Some(spec) => Some(spec),
+        None => env_spec,
+    };
+    let mut filter = match spec {
+        Some(spec) => Filter::parse(&spec).map_err(CliError)?,
+        None => Filter::default(),
+    };
+    filter.default = filter.default.max(Level::from_verbosity(verbosity));
+    let file = args.value(&["--log-file"])?.map(PathBuf::from);
+    Ok(LogArgs { filter, file })
+}
+
+fn init_log(args: LogArgs) -> Result<(), Error> {
+    let out = match args.file {
+        Some(path) => match File::create(&path) {
+            Ok(file) => Box::new(file),
+            Err(source) => return Err(Error::IO { path: path.display().to_string(), source }),
+        },
+        None => log::stderr(),
+    };
+    log::init(args.filter, out);
+    Ok(())
+}
+
+pub fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<Command, CliError> {
+    let mut args = Args::new(args);
+    let help = args.flag(&["-h", "--help"]);
+    let command = args.subcommand();
+    if help {
+        return Ok(match command.as_deref() {
+            Some("day1") => Command::HelpFor(day1::USAGE),
+            Some("day2") => Command::HelpFor(day2::USAGE),
+            Some("day3") => Command::HelpFor(day3::USAGE),
+            _ => Command::Help,
+        });
+    }
+    let command = match command.as_deref() {
+        None | Some("run") => {
+            let mut run = parse_answers_args(&mut args)?;
+            run.record = args.flag(&["--record"]);
+            Command::Run(run)
+        }
+        Some("verify") => Command::Verify(parse_answers_args(&mut args)?),
+        Some("bench") => Command::Bench(parse_bench_args(&mut args)?),
+        Some("day1") => Command::Day1(day1::parse_args(&mut args)?),
+        Some("day2") => Command::Day2(day2::parse_args(&mut args)?),
+        Some("day3") => Command::Day3(day3::parse_args(&mut args)?),
+        Some("examples") => {
+            let days = match args.value(&["-d", "--day"])? {
+                Some(days) => parse_days(&days)?,
+                None => all_days(),
+            };
+            Command::Examples(days)
+        }
+        Some("list") => Command::List,
+        Some("help") => Command::Help,
+        Some(other) => return Err(CliError(format!("unknown command '{}'", other))),
+    };
+    args.finish()?;
+    Ok(command)
+}
+
+fn parse_run_args(args: &mut Args) -> Result<RunArgs, CliError> {
+    let mut run = RunArgs::default();
+    if let Some(days) = args.value(&["-d", "--day"])? {
+        run.days = parse_days(&days)?;
+    }
+    if let Some(parts) = args.value(&["-p", "--part"])? {
+        run.parts = parse_parts(&parts)?;
+    }
+    if let Some(data) = args.value(&["--data"])? {
+        run.data = parse_data(&data)?;
+    }
+    Ok(run)
+}
+
+fn parse_answers_args(args: &mut Args) -> Result<RunArgs, CliError> {
+    let mut run = parse_run_args(args)?;
+    if let Some(answers) = args.value(&["--answers"])? {
+        run.answers = PathBuf::from(answers);
+    }
+    Ok(run)
+}
+
+fn parse_bench_args(args: &mut Args) -> Result<BenchArgs, CliError> {
+    let mut bench = BenchArgs {
+        run: parse_run_args(args)?,
+        ..BenchArgs::default()
+    };
+    let runs = args.value(&["--runs"])?;
+    let time = args.value(&["--time"])?;
+    bench.config.budget = match (runs, time) {
+        (Some(_), Some(_)) => return Err(CliError(String::from("--runs and --time can't be used together"))),
+        (Some(runs), None) => match parse_count("--runs", &runs)? {
+            0 => return Err(CliError(String::from("--runs must be at least 1"))),
+            runs => Budget::Runs(runs),
+        },
+        (None, Some(time)) => Budget::Time(parse_duration(&time)?),
+        (None, None) => bench.config.budget,
+    };
+    if let Some(warmup) = args.value(&["--warmup"])? {
+        bench.config.warmup = parse_count("--warmup", &warmup)?;
+    }
+    if let Some(json) = args.value(&["--json"])? {
+        bench.json = json;
+    }
+    Ok(bench)
+}
+
+// Stdin can only be read once, so read it up front if it's going to be needed
+// more than once
+fn buffer_stdin(data: &Data, reused: bool) -> Result<Option<String>, Error> {
+    match data {
+        Data::Stdin if reused => read_to_string("stdin", &Data::Stdin).map(Some),
+        _ => Ok(None),
+    }
+}
+
+fn load_answers(path: &Path) -> Result<Answers, i32> {
+    Answers::load(path).map_err(|err| {
+        eprintln!("error: {}", err);
+        1
+    })
+}
+
+// Runs the selected days, returning the process exit code
+pub fn run(args: RunArgs) -> i32 {
+    // Answers only annotate the results, so a bad file can be done without,
+    // unless new answers are going to be written back to it
+    let mut answers = match Answers::load(&args.answers) {
+        Ok(answers) => answers,
+        Err(err) if !args.record => {
+            eprintln!("warning: not checking answers: {}", err);
+            Answers::default()
+        }
+        Err(err) => {
+            eprintln!("error: {}", err);
+            return 1;
+        }
+    };
+    let buffer = match buffer_stdin(&args.data, args.days.len() > 1) {
+        Ok(buffer) => buffer,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            return 1;
+        }
+    };
+    let data = match &buffer {
+        Some(buffer) => Data::Str(buffer),
+        None => args.data.clone(),
+    };
+    let key = data_key(&args.data);
+    let mut recorded = 0;
+    let mut code = 0;
+    for day in args.days {
+        let solver = match solution::get(day) {
+            Some(solver) => solver,
+            None => continue,
+        };
+        println!("Day {}: {}", day, solver.title());
+        let run = match solver.run(&data, &args.parts) {
+            Ok(run) => run,
+            Err(err) => {
+                println!("  failed");
+                eprintln!("error: day {}: {}", day, err);
+                code = 1;
+                continue;
+            }
+        };
+        // Streamed days read as they parse
+        if run.read_time.is_zero() {
+            println!("  Read and parsed in {:.2?}", run.parse_time);
+        } else {
+            println!("  Read in {:.2?}", run.read_time);
+            println!("  Parsed in {:.2?}", run.parse_time);
+        }
+        for part in run.parts {
+            match part.answer {
+                Ok(ans) => {
+                    let check = answers.check(solver, &args.data, part.part, &ans);
+                    println!("  Part {}: {} in {:.2?} {}", part.part, ans, part.time, check);
+                    if let (true, Check::Unknown, Some(key)) = (args.record, &check, &key) {
+                        answers.insert(day, key, part.part, &ans);
+                        recorded += 1;
+                    }
+                }
+                Err(err) => {
+                    println!("  Part {}: failed", part.part);
+                    eprintln!("error: day {} part {}: {}", day, part.part, err);
+                    code = 1;
+                }
+            }
+        }
+    }
+    if args.record {
+        if key.is_none() {
+            eprintln!("error: answers for {} can't be recorded", get_filename("", &args.data));
+            return 1;
+        }
+        if recorded > 0 {
+            if let Err(err) = answers.save(&args.answers) {
+                eprintln!("error: {}", err);
+                return 1;
+            }
+        }
+        println!("Recorded {} new answer(s) in {}", recorded, args.answers.display());
+    }
+    code
+}
+
+// Checks the selected days against their known answers, failing on any mismatch
+fn verify(args: RunArgs) -> i32 {
+    let answers = match load_answers(&args.answers) {
+        Ok(answers) => answers,
+        Err(code) => return code,
+    };
+    let (mut correct, mut wrong, mut unknown) = (0, 0, 0);
+    for solver in args.days.into_iter().filter_map(solution::get) {
+        println!("Day {}: {}", solver.day(), solver.title());
+        // Don't bother running days that have nothing to check against
+        let known = args.parts.iter().any(|&part| answers.expected(solver, &args.data, part).is_some());
+        if !known {
+            println!("  no known answers, skipped");
+            unknown += args.parts.len();
+            continue;
+        }
+        let run = match solver.run(&args.data, &args.parts) {
+            Ok(run) => run,
+            Err(err) => {
+                println!("  failed");
+                eprintln!("error: day {}: {}", solver.day(), err);
+                wrong += args.parts.len();
+                continue;
+            }
+        };
+        for part in run.parts {
+            let check = match part.answer {
+                Ok(ans) => {
+                    let check = answers.check(solver, &args.data, part.part, &ans);
+                    println!("  Part {}: {} {}", part.part, ans, check);
+                    check
+                }
+                Err(err) => {
+                    println!("  Part {}: failed", part.part);
+                    eprintln!("error: day {} part {}: {}", solver.day(), part.part, err);
+                    Check::Wrong(String::new())
+                }
+            };
+            match check {
+                Check::Correct => correct += 1,
+                Check::Wrong(_) => wrong += 1,
+                Check::Unknown => unknown += 1,
+            }
+        }
+    }
+    println!("{} correct, {} wrong, {} unknown", correct, wrong, unknown);
+    if wrong > 0 {
+        1
+    } else {
+        0
+    }
+}
+
+fn bench(args: BenchArgs) -> i32 {
+    let buffer = match buffer_stdin(&args.run.data, true) {
+        Ok(buffer) => buffer,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            return 1;
+        }
+    };
+    let data = match &buffer {
+        Some(buffer) => Data::Str(buffer),
+        None => args.run.data,
+    };
+    let mut code = 0;
+    let mut measurements = Vec::new();
+    for solver in args.run.days.into_iter().filter_map(solution::get) {
+        match bench::bench(solver, &data, &args.run.parts, &args.config) {
+            Ok(res) => measurements.extend(res),
+            Err(err) => {
+                eprintln!("error: day {}: {}", solver.day(), err);
+                code = 1;
+            }
+        }
+    }
+    print!("{}", bench::table(&measurements));
+    if let Err(err) = bench::write_json(&args.json, &measurements) {
+        eprintln!("error: {}", err);
+        return 1;
+    }
+    println!("Results written to {}", args.json);
+    code
+}
+
+// Runs every inline example for the selected days against its expected answers
+fn examples(days: Vec<usize>) -> i32 {
+    let mut code = 0;
+    for solver in days.into_iter().filter_map(solution::get) {
+        println!("Day {}: {}", solver.day(), solver.title());
+        if solver.examples().is_empty() {
+            println!("  no examples");
+        }
+        for example in solver.examples() {
+            let run = match solver.run(&Data::Example(example.name.to_string()), &[1, 2]) {
+                Ok(run) => run,
+                Err(err) => {
+                    println!("  {}: failed", example.name);
+                    eprintln!("error: day {} example {}: {}", solver.day(), example.name, err);
+                    code = 1;
+                    continue;
+                }
+            };
+            for (part, want) in run.parts.into_iter().zip([example.part1, example.part2]) {
+                let (status, passed) = match (&part.answer, want) {
+                    (Err(err), _) => {
+                        eprintln!("error: day {} example {} part {}: {}", solver.day(), example.name, part.part, err);
+                        (String::from("failed"), false)
+                    }
+                    (Ok(ans), None) => (format!("{} (no expected answer)", ans), true),
+                    (Ok(ans), Some(want)) if ans.to_string() == want => (format!("{} ok", ans), true),
+                    (Ok(ans), Some(want)) => (format!("{} but expected {}", ans, want), false),
+                };
+                if !passed {
+                    code = 1;
+                }
+                println!("  {} part {}: {}", example.name, part.part, status);
+            }
+        }
+    }
+    code
+}
+
+fn list() -> i32 {
+    for solver in registry() {
+        let names: Vec<&str> = solver.examples().iter().map(|example| example.name).collect();
+        println!("{:>2}  {:<24} examples: {}", solver.day(), solver.title(), names.join(", "));
+    }
+    0
+}
+
+pub fn main() -> i32 {
+    let mut args = Args::new(env::args().skip(1));
+    let log_args = match parse_log_args(&mut args, env::var("AOC_LOG").ok()) {
+        Ok(log_args) => log_args,
+        Err(err) => {
+            eprintln!("error: {}\n\n{}", err, USAGE);
+            return 2;
+        }
+    };
+    if let Err(err) = init_log(log_args) {
+        eprintln!("error: {}", err);
+        return 1;
+    }
+    match parse_args(args.args) {
+        Ok(Command::Run(args)) => run(args),
+        Ok(Command::Bench(args)) => bench(args),
+        Ok(Command::Verify(args)) => verify(args),
+        Ok(Command::Day1(args)) => day1::run(args),
+        Ok(Command::Day2(args)) => day2::run(args),
+        Ok(Command::Day3(args)) => day3::run(args),
+        Ok(Command::Examples(days)) => examples(days),
+        Ok(Command::List) => list(),
+        Ok(Command::Help) => {
+            println!("{}", USAGE);
+            0
+        }
+        Ok(Command::HelpFor(usage)) => {
+            println!("{}", usage);
+            0
+        }
+        Err(err) => {
+            eprintln!("error: {}\n\n{}", err, USAGE);
+            2
+        }
+    }
+}
+
+#[cfg(test)]
+#[macro_use]
+mod tests {
+    use table_test::table_test;
+
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_parse_days() {
+        let table = vec![
+            ("5", vec![5]),
+            ("1-4", vec![1, 2, 3, 4]),
+            ("all", vec![1, 2, 3, 4, 5, 6, 7]),
+            ("6-7,1,3", vec![1, 3, 6, 7]),
+            ("2,2-3", vec![2, 3]),
+        ];
+
+        for (validator, input, expected) in table_test!(table) {
+            let actual = parse_days(input).unwrap();
+
+            validator
+                .given(input)
+                .when("parse_days")
+                .then(&format!("it should be {:?}", expected))
+                .assert_eq(expected, actual);
+        }
+    }
+
+    #[test]
+    fn parse_days_invalid() {
+        for input in ["0", "8", "4-2", "x", "1-", ""] {
+            assert!(parse_days(input).is_err(), "{} should not parse", input);
+        }
+    }
+
+    #[test]
+    fn parse_args_default() {
+        let res = parse_args(args("")).unwrap();
+        assert_eq!(Command::Run(RunArgs::default()), res);
+    }
+
+    #[test]
+    fn parse_args_run() {
+        let res = parse_args(args("run --day 5 --part 2 --data test")).unwrap();
+        let want = Command::Run(RunArgs {
+            days: vec![5],
+            parts: vec![2],
+            data: Data::Test,
+            ..RunArgs::default()
+        });
+        assert_eq!(want, res);
+
+        let res = parse_args(args("run -d 1 --data ./inputs/day1.txt")).unwrap();
+        let want = Command::Run(RunArgs {
+            days: vec![1],
+            data: Data::Path(PathBuf::from("./inputs/day1.txt")),
+            ..RunArgs::default()
+        });
+        assert_eq!(want, res);
+
+        let res = parse_args(args("-d 6 --data -")).unwrap();
+        let want = Command::Run(RunArgs {
+            days: vec![6],
+            data: Data::Stdin,
+            ..RunArgs::default()
+        });
+        assert_eq!(want, res);
+
+        let res = parse_args(args("-d 4 --data example:puzzle")).unwrap();
+        let want = Command::Run(RunArgs {
+            days: vec![4],
+            data: Data::Example(String::from("puzzle")),
+            ..RunArgs::default()
+        });
+        assert_eq!(want, res);
+
+        let res = parse_args(args("run -d 2 --answers known.toml --record")).unwrap();
+        let want = Command::Run(RunArgs {
+            days: vec![2],
+            answers: PathBuf::from("known.toml"),
+            record: true,
+            ..RunArgs::default()
+        });
+        assert_eq!(want, res);
+
+        let res = parse_args(args("verify -d 1-3 --data test")).unwrap();
+        let want = Command::Verify(RunArgs {
+            days: vec![1, 2, 3],
+            data: Data::Test,
+            ..RunArgs::default()
+        });
+        assert_eq!(want, res);
+
+        let res = parse_args(args("examples -d 2-3")).unwrap();
+        assert_eq!(Command::Examples(vec![2, 3]), res);
+
+        let res = parse_args(args("-d=1-3 -p all")).unwrap();
+        let want = Command::Run(RunArgs {
+            days: vec![1, 2, 3],
+            ..RunArgs::default()
+        });
+        assert_eq!(want, res);
+    }
+
+    #[test]
+    fn parse_args_errors() {
+        assert!(parse_args(args("run --day")).is_err());
+        assert!(parse_args(args("run --bogus")).is_err());
+        assert!(parse_args(args("frobnicate")).is_err());
+        assert!(parse_args(args("run --part 3")).is_err());
+        assert!(parse_args(args("run --data=")).is_err());
+        assert!(parse_args(args("run --runs 5")).is_err());
+        assert!(parse_args(args("bench --runs 5 --time 1s")).is_err());
+        assert!(parse_args(args("bench --runs 0")).is_err());
+        assert!(parse_args(args("bench --time soon")).is_err());
+        assert!(parse_args(args("bench --record")).is_err());
+        assert!(parse_args(args("verify --record")).is_err());
+    }
+
+    #[test]
+    fn parse_args_bench() {
+        let res = parse_args(args("bench -d 5 --runs 20 --warmup 0 --json out.json")).unwrap();
+        let want = Command::Bench(BenchArgs {
+            run: RunArgs {
+                days: vec![5],
+                ..RunArgs::default()
+            },
+            config: BenchConfig {
+                warmup: 0,
+                budget: Budget::Runs(20),
+            },
+            json: String::from("out.json"),
+        });
+        assert_eq!(want, res);
+
+        let res = parse_args(args("bench --time 1.5s")).unwrap();
+        let want = Command::Bench(BenchArgs {
+            config: BenchConfig {
+                budget: Budget::Time(Duration::from_millis(1500)),
+                ..BenchConfig::default()
+            },
+            ..BenchArgs::default()
+        });
+        assert_eq!(want, res);
+    }
+
+    #[test]
+    fn log_args() {
+        let mut cli = Args::new(args("-d 5 -vv --log day7=trace --log-file trace.log"));
+        let res = parse_log_args(&mut cli, Some(String::from("off"))).unwrap();
+        let want = LogArgs {
+            filter: Filter {
+                default: Level::Debug,
+                modules: vec![(String::from("day7"), Level::Trace)],
+            },
+            file: Some(PathBuf::from("trace.log")),
+        };
+        assert_eq!(want, res);
+        assert_eq!(Command::Run(RunArgs { days: vec![5], ..RunArgs::default() }), parse_args(cli.args).unwrap());
+
+        let res = parse_log_args(&mut Args::new(args("")), Some(String::from("day4=debug"))).unwrap();
+        assert_eq!(Level::Debug, res.filter.level("day4"));
+        assert_eq!(Level::Off, res.filter.level("day5"));
+
+        assert!(parse_log_args(&mut Args::new(args("--log loud")), None).is_err());
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(Duration::from_secs(2), parse_duration("2s").unwrap());
+        assert_eq!(Duration::from_millis(500), parse_duration("500ms").unwrap());
+        assert_eq!(Duration::from_secs(3), parse_duration("3").unwrap());
+        for input in ["", "ms", "-1s", "0s", "fast"] {
+            assert!(parse_duration(input).is_err(), "{} should not parse", input);
+        }
+    }
+}
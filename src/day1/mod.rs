@@ -7,176 +7,375 @@
 // Steps
 // read input file line by line
 
-use std::{fs::File, time::Instant, time::Duration};
-use super::util::read_lines;
-use std::io::{self};
-use super::util::get_filename;
-
-
-fn lines_to_elves(lines: io::Result<io::Lines<io::BufReader<File>>>) -> Vec<Vec<String>> {
-    let mut elves: Vec<Vec<String>> = Vec::new();
-    let mut elf: Vec<String> = Vec::new();
-    if let Ok(lines) = lines {
-        for line in lines {
-            if let Ok(item) = line {
-                if item == "" {
-                    elves.push(elf);
-                    elf = Vec::new();
-                } else {
-                    elf.push(item);
-                }
+use std::io::BufRead;
+
+use crate::error::{parse_number, Error};
+use crate::solution::{examples, Answer, Example, Solution, SolveResult};
+
+pub mod rebalance;
+pub mod subsets;
+pub mod stats;
+
+pub struct Day1;
+
+// What one elf is carrying
+#[derive(Debug, Clone, PartialEq)]
+pub struct Elf {
+    pub items: usize,
+    pub cals: isize,
+}
+
+// Reads elves one at a time, holding only the current line, so inventories
+// of any size can be summed in constant memory. A blank line ends an elf.
+pub struct Elves<R> {
+    reader: R,
+    // Name of the input, for IO errors
+    file: String,
+    line: String,
+    line_no: usize,
+    done: bool,
+}
+
+impl<R: BufRead> Elves<R> {
+    pub fn new(reader: R, file: &str) -> Elves<R> {
+        Elves {
+            reader,
+            file: file.to_string(),
+            line: String::new(),
+            line_no: 0,
+            done: false,
+        }
+    }
+
+    // Passes each item's calories to `on_item` as it's read. None once the
+    // input runs out, as a trailing blank line or empty input starts no elf.
+    fn next_elf(&mut self, mut on_item: impl FnMut(isize)) -> Result<Option<Elf>, Error> {
+        let mut elf = Elf { items: 0, cals: 0 };
+        loop {
+            self.line.clear();
+            let read = self.reader
+                .read_line(&mut self.line)
+                .map_err(|source| Error::IO { path: self.file.clone(), source })?;
+            if read == 0 {
+                self.done = true;
+                return Ok(Some(elf).filter(|elf| elf.items > 0));
+            }
+            self.line_no += 1;
+            let item = self.line.trim_end_matches(['\n', '\r']);
+            if item.is_empty() {
+                return Ok(Some(elf));
             }
+            let cals: isize = parse_number(item, item).map_err(|err| err.at_line(self.line_no))?;
+            elf.cals = match elf.cals.checked_add(cals) {
+                Some(total) => total,
+                None => return Err(Error::parse(item, item, "elf's calorie total overflows").at_line(self.line_no)),
+            };
+            elf.items += 1;
+            on_item(cals);
         }
     }
-    elves.push(elf);
-    elves
-}
 
-fn elf_cals(elf: Vec<String>) -> isize {
-    let mut cals: isize = 0;
-    for item in elf {
-        let no_cals = item.parse::<isize>().unwrap();
-        cals += no_cals;
+    // The next elf along with the calories of each of its items
+    pub fn next_with_items(&mut self) -> Option<Result<(Elf, Vec<isize>), Error>> {
+        if self.done {
+            return None;
+        }
+        let mut items = Vec::new();
+        let res = self.next_elf(|cals| items.push(cals));
+        if res.is_err() {
+            self.done = true;
+        }
+        res.transpose().map(|res| res.map(|elf| (elf, items)))
     }
-    cals
 }
 
-pub fn part1(input: super::Data) -> (isize, Duration) {
-    let now = Instant::now();
-    let mut max: isize = 0;
-    let file = get_filename("day1", input);
-    let lines = read_lines(file);
-    for elf in lines_to_elves(lines) {
-        let cals = elf_cals(elf);
-        if cals > max {
-            max = cals;
+impl<R: BufRead> Iterator for Elves<R> {
+    type Item = Result<Elf, Error>;
+
+    // Stops after the first error
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let res = self.next_elf(|_| ());
+        if res.is_err() {
+            self.done = true;
         }
+        res.transpose()
     }
-    (max, now.elapsed())
 }
 
+// Every elf in input order, for tools that need more than the totals
+pub fn inventory<R: BufRead>(reader: R, file: &str) -> Result<Vec<Elf>, Error> {
+    Elves::new(reader, file).collect()
+}
+
+// Each elf's items in input order, for tools that move items between elves
+pub fn packs<R: BufRead>(reader: R, file: &str) -> Result<Vec<Vec<isize>>, Error> {
+    let mut elves = Elves::new(reader, file);
+    let mut res = Vec::new();
+    while let Some(elf) = elves.next_with_items() {
+        res.push(elf?.1);
+    }
+    Ok(res)
+}
+
+// The `k` heaviest elves seen so far, heaviest first, as (elf, calories).
+// Elves are numbered from 1 in input order, and when two carry the same
+// amount the lower numbered elf ranks higher.
 #[derive(Debug, PartialEq)]
-struct TopThree {
-    elves: [isize; 3]
+pub struct TopK {
+    k: usize,
+    elves: Vec<(usize, isize)>,
 }
 
-impl TopThree {
-    fn init() -> TopThree {
-        TopThree { elves: [0, 0, 0] }
+impl TopK {
+    // k comes straight from the command line, so the list grows as elves
+    // arrive rather than being sized for k up front
+    pub fn new(k: usize) -> TopK {
+        TopK { k, elves: Vec::new() }
     }
 
-    fn insert(&mut self, input: isize) {
-        let mut to_input = input;
-        let mut elves: [isize; 3] = [0, 0, 0];
-        for (idx, elf) in self.elves.iter().enumerate() {
-            if to_input >= *elf {
-                elves[idx] = to_input;
-                to_input = *elf;
-            } else {
-                elves[idx] = *elf;
-            }
+    pub fn insert(&mut self, elf: usize, cals: isize) {
+        let pos = self.elves
+            .iter()
+            .position(|&(other, other_cals)| cals > other_cals || (cals == other_cals && elf < other))
+            .unwrap_or(self.elves.len());
+        if pos < self.k {
+            self.elves.insert(pos, (elf, cals));
+            self.elves.truncate(self.k);
         }
-        self.elves = elves;
     }
 
-    fn sum(&self) -> isize {
-        let mut res = 0;
-        for elf in self.elves{
-            res += elf;
-        }
-        res
+    pub fn elves(&self) -> &[(usize, isize)] {
+        &self.elves
+    }
+
+    // Wide enough that no number of elves can overflow it
+    pub fn sum(&self) -> i128 {
+        self.elves.iter().map(|&(_, cals)| cals as i128).sum()
     }
 }
 
-pub fn part2(input: super::Data) -> (isize, Duration) {
-    let now = Instant::now();
-    let mut tt = TopThree::init();
-    let file = get_filename("day1", input);
-    let lines = read_lines(file);
-    for elf in lines_to_elves(lines) {
-        let cals = elf_cals(elf);
-        tt.insert(cals)
+// Neither part needs more than the three heaviest elves, so they're all
+// that's kept as the elves stream past
+fn heaviest<R: BufRead>(reader: R, file: &str) -> Result<TopK, Error> {
+    let mut top = TopK::new(3);
+    for (idx, elf) in Elves::new(reader, file).enumerate() {
+        top.insert(idx + 1, elf?.cals);
+    }
+    Ok(top)
+}
+
+fn part1(top: &TopK) -> isize {
+    top.elves().first().map_or(0, |&(_, cals)| cals.max(0))
+}
+
+fn part2(top: &TopK) -> Result<isize, Error> {
+    let sum = top.sum();
+    isize::try_from(sum).map_err(|_| Error::solve(format!("the top three elves' total of {} is too large", sum)))
+}
+
+examples! {
+    solution: Day1,
+    puzzle: {
+        input: "1000
+2000
+3000
+
+4000
+
+5000
+6000
+
+7000
+8000
+9000
+
+10000
+",
+        part1: "24000",
+        part2: "45000",
+    },
+    two_elves: {
+        input: "300\n\n100\n100\n",
+        part1: "300",
+        part2: "500",
+    },
+}
+
+impl Solution for Day1 {
+    const DAY: usize = 1;
+    const TITLE: &'static str = "Calorie Counting";
+    const EXAMPLES: &'static [Example] = EXAMPLES;
+    // The three heaviest elves
+    type Parsed = TopK;
+
+    fn parse(&self, input: &str) -> SolveResult<Self::Parsed> {
+        heaviest(input.as_bytes(), "<input>")
+    }
+
+    const STREAMS: bool = true;
+
+    fn parse_reader(&self, reader: &mut dyn BufRead, file: &str) -> SolveResult<Self::Parsed> {
+        heaviest(reader, file)
+    }
+
+    fn part1(&self, top: &Self::Parsed) -> SolveResult<Answer> {
+        Ok(part1(top).into())
+    }
+
+    fn part2(&self, top: &Self::Parsed) -> SolveResult<Answer> {
+        Ok(part2(top)?.into())
     }
-    (tt.sum(), now.elapsed())
 }
 
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+
     use super::*;
-    use super::super::Data;
+    use crate::solution::Solver;
+    use crate::Data;
+
+    const EXAMPLE_TOTALS: [isize; 5] = [6000, 4000, 11000, 24000, 10000];
+
+    fn elves(input: &str) -> Vec<Result<Elf, Error>> {
+        Elves::new(input.as_bytes(), "<input>").collect()
+    }
+
+    fn top_k(totals: &[isize], k: usize) -> TopK {
+        let mut top = TopK::new(k);
+        for (idx, &cals) in totals.iter().enumerate() {
+            top.insert(idx + 1, cals);
+        }
+        top
+    }
+
+    #[test]
+    fn elves_stream() {
+        let cals: Vec<isize> = elves(EXAMPLES[0].input).into_iter().map(|elf| elf.unwrap().cals).collect();
+        assert_eq!(EXAMPLE_TOTALS.to_vec(), cals);
+
+        let res: Vec<Elf> = elves("1\r\n2\r\n\r\n3").into_iter().map(Result::unwrap).collect();
+        assert_eq!(vec![Elf { items: 2, cals: 3 }, Elf { items: 1, cals: 3 }], res);
+    }
+
     #[test]
-    fn test_part1() {
-        let (res, _dur) = part1(Data::Test);
-        let want = 24000;
-        assert_eq!(res, want);
+    fn elves_trailing_blank() {
+        let res: Vec<Elf> = elves("1\n\n2\n\n").into_iter().map(Result::unwrap).collect();
+        assert_eq!(vec![Elf { items: 1, cals: 1 }, Elf { items: 1, cals: 2 }], res);
+        assert_eq!(2, packs("1\n\n2\n\n".as_bytes(), "<input>").unwrap().len());
     }
 
     #[test]
-    fn lines_to_elves() {
-        let lines = read_lines("./src/day1/test.txt");
-        let res = super::lines_to_elves(lines);
-        let want = vec![
-            vec!["1000", "2000", "3000"],
-            vec!["4000"],
-            vec!["5000", "6000"],
-            vec!["7000", "8000", "9000"],
-            vec!["10000"]];
-        assert_eq!(res, want);
+    fn elves_empty() {
+        assert!(elves("").is_empty());
+        assert!(inventory("".as_bytes(), "<input>").unwrap().is_empty());
+        assert!(packs("".as_bytes(), "<input>").unwrap().is_empty());
     }
 
     #[test]
-    fn elf_cals() {
-        let elves = vec![
-            vec!["1000".to_string(), "2000".to_string(), "3000".to_string()],
-            vec!["4000".to_string()],
-            vec!["5000".to_string(), "6000".to_string()],
-            vec!["7000".to_string(), "8000".to_string(), "9000".to_string()],
-            vec!["10000".to_string()]];
-        let want: Vec<isize> = vec![6000, 4000, 11000, 24000, 10000];
-        let mut result: Vec<isize> = Vec::new();
-        for elf in elves {
-            result.push(super::elf_cals(elf));
+    fn elves_invalid() {
+        let res = elves("1000\n2000\n\n4000\n40x0\n\n5\n");
+        assert_eq!(2, res.len(), "reading should stop at the first error");
+        match &res[1] {
+            Err(Error::Parse(err)) => {
+                assert_eq!(Some(5), err.line);
+                assert_eq!("40x0", err.text);
+            }
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn elves_overflow() {
+        let res = elves(&format!("1\n\n{}\n1\n", isize::MAX));
+        match &res[1] {
+            Err(Error::Parse(err)) => {
+                assert_eq!(Some(4), err.line);
+                assert!(err.message.contains("overflows"));
+            }
+            other => panic!("expected an overflow error, got {:?}", other),
         }
-        assert_eq!(want, result);
     }
 
     #[test]
-    fn topthree_init() {
-        let res = TopThree::init();
-        let want = TopThree{elves: [0, 0, 0]};
-        assert_eq!(want, res);
+    fn test_inventory() {
+        let res = inventory(EXAMPLES[0].input.as_bytes(), "<input>").unwrap();
+        let items: Vec<usize> = res.iter().map(|elf| elf.items).collect();
+        let cals: Vec<isize> = res.iter().map(|elf| elf.cals).collect();
+        assert_eq!(vec![3, 1, 2, 3, 1], items);
+        assert_eq!(EXAMPLE_TOTALS.to_vec(), cals);
     }
 
     #[test]
-    fn topthree_insert() {
-        let mut tt = TopThree{elves: [20, 12, 8]};
-        tt.insert(16);
-        assert_eq!(TopThree{elves: [20, 16, 12]}, tt);
+    fn test_packs() {
+        let res = packs(EXAMPLES[0].input.as_bytes(), "<input>").unwrap();
+        assert_eq!(vec![vec![1000, 2000, 3000], vec![4000], vec![5000, 6000], vec![7000, 8000, 9000], vec![10000]], res);
+        assert!(packs("1\n\nx\n".as_bytes(), "<input>").is_err());
     }
 
     #[test]
-    fn topthree_insert_lower() {
-        let mut tt = TopThree{elves: [20, 12, 8]};
-        tt.insert(6);
-        assert_eq!(TopThree{elves: [20, 12, 8]}, tt);
+    fn run_streams() {
+        let run = Day1.run(&Data::Str(EXAMPLES[0].input), &[1, 2]).unwrap();
+        assert_eq!(Duration::ZERO, run.read_time);
+        let answers: Vec<String> = run.parts.into_iter().map(|part| part.answer.unwrap().to_string()).collect();
+        assert_eq!(vec!["24000", "45000"], answers);
     }
 
     #[test]
-    fn topthree_sum() {
-        let mut tt = TopThree::init();
-        assert_eq!(tt.sum(), 0);
-        tt.insert(1);
-        tt.insert(2);
-        tt.insert(3);
-        assert_eq!(tt.sum(), 6);
-        tt.insert(2);
-        assert_eq!(tt.sum(), 7);
+    fn topk_new() {
+        let res = TopK::new(3);
+        assert!(res.elves().is_empty());
+        assert_eq!(0, res.sum());
     }
 
     #[test]
-    fn part_two() {
-        let (res, _dur) = part2(Data::Test);
-        let want: isize = 45000;
-        assert_eq!(want, res);
+    fn topk_insert() {
+        let mut top = top_k(&[20, 12, 8], 3);
+        top.insert(4, 16);
+        assert_eq!(&[(1, 20), (4, 16), (2, 12)], top.elves());
+    }
+
+    #[test]
+    fn topk_insert_lower() {
+        let mut top = top_k(&[20, 12, 8], 3);
+        top.insert(4, 6);
+        assert_eq!(&[(1, 20), (2, 12), (3, 8)], top.elves());
+    }
+
+    #[test]
+    fn topk_huge_k() {
+        let top = top_k(&EXAMPLE_TOTALS, usize::MAX);
+        assert_eq!(&[(4, 24000), (3, 11000), (5, 10000), (1, 6000), (2, 4000)], top.elves());
+        assert_eq!(55000, top.sum());
+    }
+
+    #[test]
+    fn topk_sum() {
+        let mut top = TopK::new(3);
+        assert_eq!(top.sum(), 0);
+        top.insert(1, 1);
+        top.insert(2, 2);
+        top.insert(3, 3);
+        assert_eq!(top.sum(), 6);
+        top.insert(4, 2);
+        assert_eq!(top.sum(), 7);
+    }
+
+    #[test]
+    fn topk_ties() {
+        // The earlier elf wins a tie, whatever order they're inserted in
+        let mut top = TopK::new(2);
+        top.insert(5, 10);
+        top.insert(2, 10);
+        top.insert(9, 10);
+        top.insert(7, 3);
+        assert_eq!(&[(2, 10), (5, 10)], top.elves());
+
+        assert!(top_k(&[1, 2], 0).elves().is_empty());
+        let all = top_k(&EXAMPLE_TOTALS, 10);
+        assert_eq!(&[(4, 24000), (3, 11000), (5, 10000), (1, 6000), (2, 4000)], all.elves());
     }
 }
@@ -0,0 +1,393 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+
+use crate::error::Error;
+use crate::log::debug;
+
+use super::stats::sep;
+
+// Moving items between elves so the heaviest pack is as light as possible.
+// This is multiway number partitioning: NP-hard in general, so large
+// inventories get a greedy plan that's then improved, and small ones are
+// searched exhaustively.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    // Exact for inventories of up to `EXACT_ITEMS` items, heuristic above
+    Auto,
+    Exact,
+    Heuristic,
+}
+
+const EXACT_ITEMS: usize = 40;
+// The search recurses once per item
+const MAX_EXACT_ITEMS: usize = 10_000;
+// Search nodes before the exact search settles for the best plan so far
+const EXACT_NODES: usize = 20_000_000;
+// Swaps and moves tried after the greedy plan
+const IMPROVE_ROUNDS: usize = 10_000;
+
+// An item, and the elf (numbered from 1) that carried it before
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Item {
+    pub from: usize,
+    pub cals: isize,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Plan {
+    // What each elf carries afterwards. Elves keep as many of their own
+    // items as the plan allows, listed first.
+    pub elves: Vec<Vec<Item>>,
+    // Heaviest elf before, as (elf, calories)
+    pub before: (usize, i128),
+    pub after: i128,
+    // No plan can beat this: the mean load, or the largest single item
+    pub lower_bound: i128,
+    // Whether `after` is known to be the best possible
+    pub optimal: bool,
+    pub moved: usize,
+}
+
+pub fn rebalance(packs: &[Vec<isize>], mode: Mode) -> Result<Plan, Error> {
+    if packs.is_empty() {
+        return Err(Error::solve("there are no elves to rebalance"));
+    }
+    // Heaviest first, which is the order both the greedy plan and the search want
+    let mut items: Vec<Item> = Vec::new();
+    for (idx, pack) in packs.iter().enumerate() {
+        for &cals in pack {
+            if cals < 0 {
+                return Err(Error::solve(format!("elf {} carries an item of {} calories; loads can't be balanced with negative items", idx + 1, cals)));
+            }
+            items.push(Item { from: idx + 1, cals });
+        }
+    }
+    items.sort_by_key(|item| Reverse(item.cals));
+
+    let bins = packs.len();
+    let total: i128 = items.iter().map(|item| item.cals as i128).sum();
+    let largest = items.first().map_or(0, |item| item.cals as i128);
+    let lower_bound = (total + bins as i128 - 1).div_euclid(bins as i128).max(largest);
+    let before = packs
+        .iter()
+        .map(|pack| pack.iter().map(|&cals| cals as i128).sum::<i128>())
+        .enumerate()
+        .fold((1, 0), |best, (idx, load)| if load > best.1 { (idx + 1, load) } else { best });
+
+    let exact = match mode {
+        Mode::Auto => items.len() <= EXACT_ITEMS,
+        Mode::Exact if items.len() > MAX_EXACT_ITEMS => {
+            return Err(Error::solve(format!(
+                "exact rebalancing handles at most {} items, but there are {}",
+                MAX_EXACT_ITEMS, items.len()
+            )));
+        }
+        Mode::Exact => true,
+        Mode::Heuristic => false,
+    };
+
+    // Improving the packs as they are usually moves far fewer items than a
+    // fresh greedy plan, so it's kept unless the greedy plan is lighter
+    let mut current: Vec<usize> = items.iter().map(|item| item.from - 1).collect();
+    let mut current_loads = vec![0; bins];
+    for item in &items {
+        current_loads[item.from - 1] += item.cals as i128;
+    }
+    improve(&items, &mut current, &mut current_loads);
+    let (mut assign, mut loads) = greedy(&items, bins);
+    improve(&items, &mut assign, &mut loads);
+    debug!("improved packs: heaviest {}, greedy plan: heaviest {}", max_load(&current_loads), max_load(&loads));
+    if max_load(&current_loads) <= max_load(&loads) {
+        (assign, loads) = (current, current_loads);
+    }
+    let mut after = max_load(&loads);
+    let mut optimal = after <= lower_bound;
+    if exact && !optimal {
+        let mut search = Search {
+            items: &items,
+            loads: vec![0; bins],
+            assign: vec![0; items.len()],
+            best: after,
+            best_assign: assign.clone(),
+            lower_bound,
+            remaining: remaining(&items),
+            nodes: 0,
+        };
+        search.search(0);
+        debug!("exact search: {} nodes, heaviest {}", search.nodes, search.best);
+        optimal = search.best <= lower_bound || search.nodes < EXACT_NODES;
+        after = search.best;
+        assign = search.best_assign;
+    }
+
+    let (elves, moved) = relabel(&items, &assign, bins);
+    Ok(Plan { elves, before, after, lower_bound, optimal, moved })
+}
+
+fn max_load(loads: &[i128]) -> i128 {
+    loads.iter().copied().max().unwrap_or(0)
+}
+
+// Longest processing time first: each item, heaviest first, goes to the
+// lightest elf so far. Returns each item's elf and the resulting loads.
+fn greedy(items: &[Item], bins: usize) -> (Vec<usize>, Vec<i128>) {
+    let mut loads = vec![0; bins];
+    let mut assign = Vec::with_capacity(items.len());
+    for item in items {
+        let lightest = (0..bins).min_by_key(|&bin| loads[bin]).unwrap_or(0);
+        loads[lightest] += item.cals as i128;
+        assign.push(lightest);
+    }
+    (assign, loads)
+}
+
+// Repeatedly moves an item off the heaviest elf, or swaps one of its items
+// for a lighter one, while that leaves both elves lighter than it was. Every
+// step shrinks the sum of squared loads, so this always stops.
+fn improve(items: &[Item], assign: &mut [usize], loads: &mut [i128]) {
+    for _ in 0..IMPROVE_ROUNDS {
+        let heaviest = (0..loads.len()).max_by_key(|&bin| loads[bin]).unwrap_or(0);
+        let lightest = (0..loads.len()).min_by_key(|&bin| loads[bin]).unwrap_or(0);
+        let gap = loads[heaviest] - loads[lightest];
+        let carried: Vec<usize> = (0..items.len()).filter(|&idx| assign[idx] == heaviest).collect();
+
+        // Moving `cals` across a gap helps if it's less than the gap, and
+        // most when it's half of it
+        let best_move = carried
+            .iter()
+            .copied()
+            .filter(|&idx| items[idx].cals > 0 && (items[idx].cals as i128) < gap)
+            .min_by_key(|&idx| (2 * items[idx].cals as i128 - gap).abs());
+        if let Some(idx) = best_move {
+            loads[heaviest] -= items[idx].cals as i128;
+            loads[lightest] += items[idx].cals as i128;
+            assign[idx] = lightest;
+            continue;
+        }
+
+        let mut best_swap: Option<(i128, usize, usize)> = None;
+        for &mine in &carried {
+            for (theirs, &bin) in assign.iter().enumerate() {
+                let diff = items[mine].cals as i128 - items[theirs].cals as i128;
+                let gap = loads[heaviest] - loads[bin];
+                if bin == heaviest || diff <= 0 || diff >= gap {
+                    continue;
+                }
+                let score = (2 * diff - gap).abs();
+                if best_swap.is_none_or(|(best, _, _)| score < best) {
+                    best_swap = Some((score, mine, theirs));
+                }
+            }
+        }
+        match best_swap {
+            Some((_, mine, theirs)) => {
+                let bin = assign[theirs];
+                let diff = items[mine].cals as i128 - items[theirs].cals as i128;
+                loads[heaviest] -= diff;
+                loads[bin] += diff;
+                assign[mine] = bin;
+                assign[theirs] = heaviest;
+            }
+            None => return,
+        }
+    }
+}
+
+// Branch and bound over every assignment of items to elves, pruning any
+// that can't beat the best plan found so far
+struct Search<'a> {
+    items: &'a [Item],
+    loads: Vec<i128>,
+    assign: Vec<usize>,
+    best: i128,
+    best_assign: Vec<usize>,
+    lower_bound: i128,
+    // Calories of the items from each index on
+    remaining: Vec<i128>,
+    nodes: usize,
+}
+
+impl Search<'_> {
+    // Returns true once there's no point searching further
+    fn search(&mut self, idx: usize) -> bool {
+        if idx == self.items.len() {
+            self.best = max_load(&self.loads);
+            self.best_assign = self.assign.clone();
+            return self.best <= self.lower_bound;
+        }
+        if self.nodes >= EXACT_NODES {
+            return true;
+        }
+        self.nodes += 1;
+
+        // Everything left has to fit under the best so far
+        let room: i128 = self.loads.iter().map(|load| self.best - 1 - load).sum();
+        if room < self.remaining[idx] {
+            return false;
+        }
+        let cals = self.items[idx].cals as i128;
+        // Elves with the same load are interchangeable, so only one of them
+        // needs trying; lightest first finds good plans sooner
+        let mut bins: Vec<usize> = (0..self.loads.len()).filter(|&bin| self.loads[bin] + cals < self.best).collect();
+        bins.sort_by_key(|&bin| (self.loads[bin], bin));
+        bins.dedup_by_key(|bin| self.loads[*bin]);
+        for bin in bins {
+            // The best may have improved since the list was made
+            if self.loads[bin] + cals >= self.best {
+                continue;
+            }
+            self.loads[bin] += cals;
+            self.assign[idx] = bin;
+            let done = self.search(idx + 1);
+            self.loads[bin] -= cals;
+            if done {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+fn remaining(items: &[Item]) -> Vec<i128> {
+    let mut res = vec![0; items.len() + 1];
+    for idx in (0..items.len()).rev() {
+        res[idx] = res[idx + 1] + items[idx].cals as i128;
+    }
+    res
+}
+
+// Hands the planned packs out so that as many items as possible stay with
+// the elf that already has them, and counts the items that still move
+fn relabel(items: &[Item], assign: &[usize], bins: usize) -> (Vec<Vec<Item>>, usize) {
+    let mut overlap: HashMap<(usize, usize), usize> = HashMap::new();
+    for (item, &bin) in items.iter().zip(assign) {
+        *overlap.entry((bin, item.from - 1)).or_insert(0) += 1;
+    }
+    let mut pairs: Vec<((usize, usize), usize)> = overlap.into_iter().collect();
+    pairs.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then(a.cmp(b)));
+
+    let mut elf_for: Vec<Option<usize>> = vec![None; bins];
+    let mut taken = vec![false; bins];
+    for ((bin, elf), _) in pairs {
+        if elf_for[bin].is_none() && !taken[elf] {
+            elf_for[bin] = Some(elf);
+            taken[elf] = true;
+        }
+    }
+    let mut free = (0..bins).filter(|&elf| !taken[elf]);
+    let elf_for: Vec<usize> = elf_for
+        .into_iter()
+        .map(|elf| elf.unwrap_or_else(|| free.next().unwrap_or(0)))
+        .collect();
+
+    let mut elves: Vec<Vec<Item>> = vec![Vec::new(); bins];
+    let mut moved = 0;
+    for (item, &bin) in items.iter().zip(assign) {
+        let elf = elf_for[bin];
+        if item.from != elf + 1 {
+            moved += 1;
+        }
+        elves[elf].push(*item);
+    }
+    for (idx, pack) in elves.iter_mut().enumerate() {
+        pack.sort_by_key(|item| (item.from != idx + 1, item.from, -item.cals));
+    }
+    (elves, moved)
+}
+
+impl Plan {
+    pub fn load(&self, elf: usize) -> i128 {
+        self.elves[elf - 1].iter().map(|item| item.cals as i128).sum()
+    }
+
+    pub fn items(&self) -> usize {
+        self.elves.iter().map(Vec::len).sum()
+    }
+
+    pub fn text(&self) -> String {
+        let mut res = String::new();
+        let saved = self.before.1 - self.after;
+        let percent = if self.before.1 > 0 { saved as f64 * 100.0 / self.before.1 as f64 } else { 0.0 };
+        let quality = if self.optimal {
+            String::from("optimal")
+        } else {
+            format!("best found, no plan can beat {}", sep(self.lower_bound))
+        };
+        writeln!(res, "Heaviest before:  {} calories (elf {})", sep(self.before.1), self.before.0).unwrap();
+        writeln!(res, "Heaviest after:   {} calories ({})", sep(self.after), quality).unwrap();
+        writeln!(res, "Improvement:      {} calories ({:.1}%)", sep(saved), percent).unwrap();
+        writeln!(res, "Items moved:      {} of {}", self.moved, self.items()).unwrap();
+
+        writeln!(res, "\nNew packs:").unwrap();
+        let width = self.elves.len().to_string().len();
+        for (idx, pack) in self.elves.iter().enumerate() {
+            let items: Vec<String> = pack
+                .iter()
+                .map(|item| match item.from {
+                    from if from == idx + 1 => sep(item.cals as i128),
+                    from => format!("{} (from elf {})", sep(item.cals as i128), from),
+                })
+                .collect();
+            let items = if items.is_empty() { String::from("nothing") } else { items.join(" + ") };
+            writeln!(res, "  elf {:>width$}: {} = {}", idx + 1, sep(self.load(idx + 1)), items, width = width).unwrap();
+        }
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day1::{packs, EXAMPLES};
+
+    fn loads(plan: &Plan) -> Vec<i128> {
+        (1..=plan.elves.len()).map(|elf| plan.load(elf)).collect()
+    }
+
+    #[test]
+    fn example_plan() {
+        let packs = packs(EXAMPLES[0].input.as_bytes(), "<input>").unwrap();
+        for mode in [Mode::Auto, Mode::Exact, Mode::Heuristic] {
+            let plan = rebalance(&packs, mode).unwrap();
+            assert_eq!((4, 24000), plan.before);
+            assert_eq!(11000, plan.after);
+            assert!(plan.optimal);
+            assert_eq!(vec![11000; 5], loads(&plan));
+            assert_eq!(10, plan.items());
+        }
+        // Elf 3's 5,000 + 6,000 is already a perfect pack, so it stays put
+        let plan = rebalance(&packs, Mode::Auto).unwrap();
+        assert_eq!(vec![Item { from: 3, cals: 6000 }, Item { from: 3, cals: 5000 }], plan.elves[2]);
+        assert_eq!(5, plan.moved);
+        assert!(plan.text().contains("Improvement:      13,000 calories (54.2%)"));
+    }
+
+    #[test]
+    fn improve_and_search() {
+        // Greedy alone gives 7 here; one swap brings it to 6
+        let packs = vec![vec![3, 3, 2, 2, 2], vec![]];
+        let plan = rebalance(&packs, Mode::Heuristic).unwrap();
+        assert_eq!(6, plan.after);
+        assert_eq!(vec![6, 6], loads(&plan));
+        assert_eq!(2, plan.moved);
+
+        // Swaps and moves get stuck at 44; only the search finds 42
+        let packs = vec![vec![6, 21, 8], vec![16, 21], vec![30, 17, 6]];
+        let heuristic = rebalance(&packs, Mode::Heuristic).unwrap();
+        assert_eq!((44, 42, false), (heuristic.after, heuristic.lower_bound, heuristic.optimal));
+        let exact = rebalance(&packs, Mode::Auto).unwrap();
+        assert_eq!((42, true), (exact.after, exact.optimal));
+        assert_eq!(vec![42, 41, 42], loads(&exact));
+    }
+
+    #[test]
+    fn rebalance_errors() {
+        assert!(rebalance(&[], Mode::Auto).is_err());
+        assert!(rebalance(&[vec![5, -1]], Mode::Auto).is_err());
+
+        let plan = rebalance(&[vec![], vec![]], Mode::Exact).unwrap();
+        assert_eq!((0, 0, true), (plan.after, plan.moved, plan.optimal));
+        assert!(plan.text().contains("elf 2: 0 = nothing"));
+    }
+}
@@ -0,0 +1,220 @@
+use std::fmt::Write as _;
+
+use separator::Separatable;
+
+use super::Elf;
+
+// Summary of the whole calorie inventory. Elves are numbered from 1, and
+// ties for heaviest or lightest go to the lower numbered elf.
+#[derive(Debug, PartialEq)]
+pub struct Stats {
+    pub elves: usize,
+    pub total: i128,
+    pub mean: f64,
+    pub median: f64,
+    // (percentile, calories), nearest-rank
+    pub percentiles: Vec<(usize, isize)>,
+    pub heaviest: (usize, isize),
+    pub lightest: (usize, isize),
+    pub items: usize,
+    pub min_items: usize,
+    pub max_items: usize,
+    pub histogram: Vec<Bin>,
+}
+
+// Elves carrying `lower..=upper` calories
+#[derive(Debug, PartialEq)]
+pub struct Bin {
+    pub lower: i128,
+    pub upper: i128,
+    pub count: usize,
+}
+
+const PERCENTILES: [usize; 5] = [10, 25, 75, 90, 99];
+
+pub fn stats(elves: &[Elf], bins: usize) -> Option<Stats> {
+    let first = elves.first()?;
+    let mut sorted: Vec<isize> = elves.iter().map(|elf| elf.cals).collect();
+    sorted.sort();
+    let count = sorted.len();
+    let total: i128 = sorted.iter().map(|&cals| cals as i128).sum();
+    let median = if count.is_multiple_of(2) {
+        (sorted[count/2 - 1] as f64 + sorted[count/2] as f64) / 2.0
+    } else {
+        sorted[count/2] as f64
+    };
+    let percentiles = PERCENTILES
+        .iter()
+        .map(|&p| (p, sorted[((count * p).div_ceil(100)).max(1) - 1]))
+        .collect();
+
+    let mut heaviest = (1, first.cals);
+    let mut lightest = (1, first.cals);
+    for (idx, elf) in elves.iter().enumerate() {
+        if elf.cals > heaviest.1 {
+            heaviest = (idx + 1, elf.cals);
+        }
+        if elf.cals < lightest.1 {
+            lightest = (idx + 1, elf.cals);
+        }
+    }
+
+    Some(Stats {
+        elves: count,
+        total,
+        mean: total as f64 / count as f64,
+        median,
+        percentiles,
+        heaviest,
+        lightest,
+        items: elves.iter().map(|elf| elf.items).sum(),
+        min_items: elves.iter().map(|elf| elf.items).min().unwrap_or(0),
+        max_items: elves.iter().map(|elf| elf.items).max().unwrap_or(0),
+        histogram: histogram(&sorted, bins),
+    })
+}
+
+// Equal width bins from the lightest load to the heaviest
+fn histogram(sorted: &[isize], bins: usize) -> Vec<Bin> {
+    let (min, max) = match (sorted.first(), sorted.last()) {
+        (Some(&min), Some(&max)) if bins > 0 => (min, max),
+        _ => return Vec::new(),
+    };
+    // Widened so extreme loads can't overflow the arithmetic
+    let (min, max) = (min as i128, max as i128);
+    let span = max - min + 1;
+    let width = (span + bins as i128 - 1) / bins as i128;
+    let mut res: Vec<Bin> = Vec::new();
+    let mut lower = min;
+    while lower <= max {
+        res.push(Bin { lower, upper: lower + width - 1, count: 0 });
+        lower += width;
+    }
+    for &cals in sorted {
+        res[((cals as i128 - min) / width) as usize].count += 1;
+    }
+    res
+}
+
+// Thousands separators, for numbers too wide for `Separatable`
+pub(super) fn sep(n: i128) -> String {
+    let digits = n.unsigned_abs().to_string();
+    let mut res = String::from(if n < 0 { "-" } else { "" });
+    for (idx, digit) in digits.chars().enumerate() {
+        if idx > 0 && (digits.len() - idx).is_multiple_of(3) {
+            res.push(',');
+        }
+        res.push(digit);
+    }
+    res
+}
+
+fn one_place(n: f64) -> String {
+    ((n * 10.0).round() / 10.0).separated_string()
+}
+
+impl Stats {
+    pub fn mean_items(&self) -> f64 {
+        self.items as f64 / self.elves as f64
+    }
+
+    pub fn text(&self) -> String {
+        let mut res = String::new();
+        let percentiles: Vec<String> = self.percentiles
+            .iter()
+            .map(|(p, cals)| format!("p{} {}", p, sep(*cals as i128)))
+            .collect();
+        writeln!(res, "Elves:        {}", self.elves).unwrap();
+        writeln!(res, "Total:        {} calories", sep(self.total)).unwrap();
+        writeln!(res, "Mean:         {}", one_place(self.mean)).unwrap();
+        writeln!(res, "Median:       {}", one_place(self.median)).unwrap();
+        writeln!(res, "Percentiles:  {}", percentiles.join(", ")).unwrap();
+        writeln!(res, "Heaviest:     elf {} with {}", self.heaviest.0, sep(self.heaviest.1 as i128)).unwrap();
+        writeln!(res, "Lightest:     elf {} with {}", self.lightest.0, sep(self.lightest.1 as i128)).unwrap();
+        writeln!(
+            res,
+            "Items:        {} (per elf: min {}, mean {}, max {})",
+            self.items.separated_string(), self.min_items, one_place(self.mean_items()), self.max_items
+        ).unwrap();
+
+        writeln!(res, "\nCalories per elf:").unwrap();
+        let labels: Vec<String> = self.histogram
+            .iter()
+            .map(|bin| format!("{} - {}", sep(bin.lower), sep(bin.upper)))
+            .collect();
+        let label_width = labels.iter().map(String::len).max().unwrap_or(0);
+        let most = self.histogram.iter().map(|bin| bin.count).max().unwrap_or(0).max(1);
+        for (label, bin) in labels.iter().zip(&self.histogram) {
+            // Scale bars to at most 40 characters, but never hide a non-empty bin
+            let bar = (bin.count * 40).div_ceil(most);
+            writeln!(res, "  {:>width$} | {} {}", label, "#".repeat(bar), bin.count, width = label_width).unwrap();
+        }
+        res
+    }
+
+    pub fn json(&self) -> String {
+        let percentiles: Vec<String> = self.percentiles
+            .iter()
+            .map(|(p, cals)| format!("\"p{}\": {}", p, cals))
+            .collect();
+        let bins: Vec<String> = self.histogram
+            .iter()
+            .map(|bin| format!("\n    {{\"from\": {}, \"to\": {}, \"elves\": {}}}", bin.lower, bin.upper, bin.count))
+            .collect();
+        let mut res = String::from("{\n");
+        writeln!(res, "  \"elves\": {},", self.elves).unwrap();
+        writeln!(res, "  \"total\": {},", self.total).unwrap();
+        writeln!(res, "  \"mean\": {},", self.mean).unwrap();
+        writeln!(res, "  \"median\": {},", self.median).unwrap();
+        writeln!(res, "  \"percentiles\": {{{}}},", percentiles.join(", ")).unwrap();
+        writeln!(res, "  \"heaviest\": {{\"elf\": {}, \"calories\": {}}},", self.heaviest.0, self.heaviest.1).unwrap();
+        writeln!(res, "  \"lightest\": {{\"elf\": {}, \"calories\": {}}},", self.lightest.0, self.lightest.1).unwrap();
+        writeln!(
+            res,
+            "  \"items\": {{\"total\": {}, \"min\": {}, \"mean\": {}, \"max\": {}}},",
+            self.items, self.min_items, self.mean_items(), self.max_items
+        ).unwrap();
+        writeln!(res, "  \"histogram\": [{}\n  ]", bins.join(",")).unwrap();
+        res.push_str("}\n");
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day1::{inventory, EXAMPLES};
+
+    #[test]
+    fn example_stats() {
+        let res = stats(&inventory(EXAMPLES[0].input.as_bytes(), "<input>").unwrap(), 4).unwrap();
+        assert_eq!(5, res.elves);
+        assert_eq!(55000, res.total);
+        assert_eq!(11000.0, res.mean);
+        assert_eq!(10000.0, res.median);
+        assert_eq!(vec![(10, 4000), (25, 6000), (75, 11000), (90, 24000), (99, 24000)], res.percentiles);
+        assert_eq!((4, 24000), res.heaviest);
+        assert_eq!((2, 4000), res.lightest);
+        assert_eq!((10, 1, 3), (res.items, res.min_items, res.max_items));
+        let counts: Vec<(i128, usize)> = res.histogram.iter().map(|bin| (bin.lower, bin.count)).collect();
+        assert_eq!(vec![(4000, 2), (9001, 2), (14002, 0), (19003, 1)], counts);
+    }
+
+    #[test]
+    fn ties_and_edges() {
+        let elves = vec![Elf { items: 1, cals: 7 }, Elf { items: 2, cals: 7 }];
+        let res = stats(&elves, 10).unwrap();
+        assert_eq!((1, 7), res.heaviest);
+        assert_eq!((1, 7), res.lightest);
+        assert_eq!(vec![Bin { lower: 7, upper: 7, count: 2 }], res.histogram);
+        assert!(stats(&[], 10).is_none());
+    }
+
+    #[test]
+    fn reports() {
+        let res = stats(&inventory(EXAMPLES[0].input.as_bytes(), "<input>").unwrap(), 4).unwrap();
+        assert!(res.text().contains("Heaviest:     elf 4 with 24,000"));
+        assert!(res.text().contains("   4,000 - 9,000 | ######################################## 2\n"));
+        assert!(res.json().contains("\"heaviest\": {\"elf\": 4, \"calories\": 24000}"));
+    }
+}
@@ -0,0 +1,303 @@
+use std::fmt::Write as _;
+
+use crate::error::Error;
+use crate::log::debug;
+
+use super::stats::sep;
+
+// Groups of elves whose combined calories come closest to a target; exact
+// matches first. Subset sum is NP-hard, so the search is depth first with
+// pruning, and gives up after `SEARCH_NODES` groups.
+#[derive(Debug, PartialEq)]
+pub struct Found {
+    pub target: i128,
+    // Closest first, then fewest elves, then by elf numbers. When more
+    // groups tie than were asked for, the first found are kept.
+    pub groups: Vec<Group>,
+    // False if the search gave up, so closer groups may exist
+    pub complete: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Group {
+    // Elf numbers, from 1 in input order, ascending
+    pub elves: Vec<usize>,
+    pub total: i128,
+}
+
+const SEARCH_NODES: usize = 10_000_000;
+// Bits in the table of reachable sums; above this the search goes without
+const TABLE_BITS: usize = 1 << 28;
+
+impl Group {
+    pub fn distance(&self, target: i128) -> i128 {
+        (self.total - target).abs()
+    }
+}
+
+// Up to `count` groups of at most `max_elves` elves (any number if None)
+pub fn closest(totals: &[isize], target: i128, max_elves: Option<usize>, count: usize) -> Result<Found, Error> {
+    if let Some((idx, cals)) = totals.iter().enumerate().find(|(_, &cals)| cals < 0) {
+        return Err(Error::solve(format!("elf {} carries {} calories; groups can't be found with negative totals", idx + 1, cals)));
+    }
+    // Heaviest first, so the most promising groups are tried early and the
+    // heaviest k remaining bound what k more elves can add
+    let mut elves: Vec<(usize, i128)> = totals.iter().enumerate().map(|(idx, &cals)| (idx + 1, cals as i128)).collect();
+    elves.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    let mut heaviest = vec![0; elves.len() + 1];
+    for (idx, &(_, cals)) in elves.iter().enumerate() {
+        heaviest[idx + 1] = heaviest[idx] + cals;
+    }
+
+    let mut search = Search {
+        elves: &elves,
+        heaviest,
+        reachable: Reachable::new(&elves, target),
+        target,
+        count,
+        chosen: Vec::new(),
+        groups: Vec::new(),
+        nodes: 0,
+    };
+    let limit = max_elves.unwrap_or(elves.len()).min(elves.len());
+    if count > 0 && limit > 0 {
+        search.search(0, 0, limit);
+    }
+    debug!("searched {} groups", search.nodes);
+    Ok(Found {
+        target,
+        complete: search.nodes < SEARCH_NODES,
+        groups: search.groups,
+    })
+}
+
+// For each suffix of the elves, which totals up to a cap some group of them
+// can make. Built only when it fits in `TABLE_BITS`.
+struct Reachable {
+    cap: usize,
+    words: usize,
+    bits: Vec<u64>,
+}
+
+impl Reachable {
+    fn new(elves: &[(usize, i128)], target: i128) -> Option<Reachable> {
+        // Totals beyond twice the target are never closer than nothing at all
+        let total: i128 = elves.iter().map(|&(_, cals)| cals).sum();
+        let cap = usize::try_from((2 * target).min(total)).ok()?;
+        let words = cap / 64 + 1;
+        if words.checked_mul(64 * (elves.len() + 1))? > TABLE_BITS {
+            debug!("too many totals to tabulate, searching without a table");
+            return None;
+        }
+        let mut bits = vec![0u64; words * (elves.len() + 1)];
+        bits[words * elves.len()] = 1;
+        for idx in (0..elves.len()).rev() {
+            let (done, row) = bits.split_at_mut(words * (idx + 1));
+            let row = &row[..words];
+            let this = &mut done[words * idx..];
+            this.copy_from_slice(row);
+            if let Ok(shift) = usize::try_from(elves[idx].1) {
+                shift_or(this, row, shift);
+            }
+        }
+        Some(Reachable { cap, words, bits })
+    }
+
+    // Whether elves from `idx` on can add something in `lower..=upper`.
+    // Anything past the cap is unknown, so assumed possible.
+    fn any(&self, idx: usize, lower: i128, upper: i128) -> bool {
+        if upper > self.cap as i128 {
+            return true;
+        }
+        if upper < lower || upper < 0 {
+            return false;
+        }
+        let (lower, upper) = (lower.max(0) as usize, upper as usize);
+        let row = &self.bits[self.words * idx..self.words * (idx + 1)];
+        (lower / 64..=upper / 64).any(|word| {
+            let mut mask = u64::MAX;
+            if word == lower / 64 {
+                mask &= u64::MAX << (lower % 64);
+            }
+            if word == upper / 64 {
+                mask &= u64::MAX >> (63 - upper % 64);
+            }
+            row[word] & mask != 0
+        })
+    }
+}
+
+// `dest |= src << shift`, dropping bits shifted past the end
+fn shift_or(dest: &mut [u64], src: &[u64], shift: usize) {
+    let (words, bits) = (shift / 64, shift % 64);
+    for idx in (words..dest.len()).rev() {
+        let mut word = src[idx - words] << bits;
+        if bits > 0 && idx > words {
+            word |= src[idx - words - 1] >> (64 - bits);
+        }
+        dest[idx] |= word;
+    }
+}
+
+struct Search<'a> {
+    elves: &'a [(usize, i128)],
+    // Sum of the first n elves, which are the heaviest
+    heaviest: Vec<i128>,
+    reachable: Option<Reachable>,
+    target: i128,
+    count: usize,
+    chosen: Vec<usize>,
+    groups: Vec<Group>,
+    nodes: usize,
+}
+
+impl Search<'_> {
+    // Distances a new group must beat, or None while there's still room
+    fn worst(&self) -> Option<i128> {
+        if self.groups.len() < self.count {
+            None
+        } else {
+            self.groups.last().map(|group| group.distance(self.target))
+        }
+    }
+
+    // Tries adding each elf from `start` on to the chosen group, then up to
+    // `room - 1` more after it
+    fn search(&mut self, start: usize, sum: i128, room: usize) {
+        for idx in start..self.elves.len() {
+            if self.nodes >= SEARCH_NODES {
+                return;
+            }
+            self.nodes += 1;
+            let total = sum + self.elves[idx].1;
+            let worst = self.worst();
+            if worst.is_some_and(|worst| total - self.target >= worst) {
+                // Lighter elves come later, so they may still fit
+                continue;
+            }
+            self.chosen.push(idx);
+            self.record(total);
+            if room > 1 && self.promising(idx + 1, total, room - 1) {
+                self.search(idx + 1, total, room - 1);
+            }
+            self.chosen.pop();
+        }
+    }
+
+    // Whether adding up to `room` elves from `start` on could beat the worst
+    // group kept so far
+    fn promising(&self, start: usize, sum: i128, room: usize) -> bool {
+        if start >= self.elves.len() {
+            return false;
+        }
+        let worst = match self.worst() {
+            Some(worst) => worst,
+            None => return true,
+        };
+        let most = self.heaviest[(start + room).min(self.elves.len())] - self.heaviest[start];
+        let (lower, upper) = (self.target - worst + 1 - sum, (self.target + worst - 1 - sum).min(most));
+        if upper < lower.max(0) {
+            return false;
+        }
+        match &self.reachable {
+            Some(reachable) => reachable.any(start, lower, upper),
+            None => true,
+        }
+    }
+
+    fn record(&mut self, total: i128) {
+        let mut elves: Vec<usize> = self.chosen.iter().map(|&idx| self.elves[idx].0).collect();
+        elves.sort();
+        let group = Group { elves, total };
+        let key = |group: &Group| (group.distance(self.target), group.elves.len());
+        let pos = self.groups
+            .iter()
+            .position(|other| (key(&group), &group.elves) < (key(other), &other.elves))
+            .unwrap_or(self.groups.len());
+        if pos < self.count {
+            self.groups.insert(pos, group);
+            self.groups.truncate(self.count);
+        }
+    }
+}
+
+impl Found {
+    pub fn text(&self, totals: &[isize]) -> String {
+        let mut res = String::new();
+        if self.groups.is_empty() {
+            writeln!(res, "No groups of elves found").unwrap();
+            return res;
+        }
+        let exact = self.groups.iter().filter(|group| group.total == self.target).count();
+        match exact {
+            0 => writeln!(res, "No group makes exactly {} calories; closest:", sep(self.target)).unwrap(),
+            _ => writeln!(res, "{} of these groups make exactly {} calories:", exact, sep(self.target)).unwrap(),
+        }
+        for group in &self.groups {
+            let off = match group.total - self.target {
+                0 => String::from("exact"),
+                diff if diff > 0 => format!("+{}", sep(diff)),
+                diff => sep(diff),
+            };
+            let elves: Vec<String> = group.elves.iter().map(usize::to_string).collect();
+            let cals: Vec<String> = group.elves.iter().map(|&elf| sep(totals[elf - 1] as i128)).collect();
+            writeln!(
+                res,
+                "  {} ({}): elves {} = {}",
+                sep(group.total), off, elves.join(", "), cals.join(" + ")
+            ).unwrap();
+        }
+        if !self.complete {
+            writeln!(res, "The search stopped early, so closer groups may exist").unwrap();
+        }
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOTALS: [isize; 5] = [6000, 4000, 11000, 24000, 10000];
+
+    fn groups(found: &Found) -> Vec<(Vec<usize>, i128)> {
+        found.groups.iter().map(|group| (group.elves.clone(), group.total)).collect()
+    }
+
+    #[test]
+    fn exact_groups() {
+        let found = closest(&TOTALS, 21000, None, 3).unwrap();
+        assert!(found.complete);
+        // The exact matches, then the nearest miss
+        let want = vec![(vec![3, 5], 21000), (vec![1, 2, 3], 21000), (vec![1, 2, 5], 20000)];
+        assert_eq!(want, groups(&found));
+
+        let found = closest(&TOTALS, 21000, Some(2), 1).unwrap();
+        assert_eq!(vec![(vec![3, 5], 21000)], groups(&found));
+        assert!(found.text(&TOTALS).contains("  21,000 (exact): elves 3, 5 = 11,000 + 10,000"));
+    }
+
+    #[test]
+    fn closest_groups() {
+        let found = closest(&TOTALS, 100, None, 2).unwrap();
+        assert_eq!(vec![(vec![2], 4000), (vec![1], 6000)], groups(&found));
+
+        let found = closest(&TOTALS, 60000, Some(3), 1).unwrap();
+        assert_eq!(vec![(vec![3, 4, 5], 45000)], groups(&found));
+        assert!(found.text(&TOTALS).contains("45,000 (-15,000)"));
+
+        assert!(closest(&TOTALS, 5, None, 0).unwrap().groups.is_empty());
+        assert!(closest(&[5, -1], 5, None, 1).is_err());
+    }
+
+    #[test]
+    fn many_elves() {
+        // 300 elves carrying 1,000, 1,001, ...
+        let totals: Vec<isize> = (1000..1300).collect();
+        let found = closest(&totals, 2001, Some(2), 3).unwrap();
+        assert_eq!(vec![(vec![1, 2], 2001), (vec![1, 3], 2002), (vec![1, 4], 2003)], groups(&found));
+        let found = closest(&totals, 123_456, None, 5).unwrap();
+        assert!(found.complete);
+        assert_eq!(5, found.groups.iter().filter(|group| group.total == 123_456).count());
+    }
+}
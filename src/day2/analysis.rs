@@ -0,0 +1,446 @@
+use std::cmp::Ordering;
+use std::fmt::{self, Write as _};
+
+use crate::error::Error;
+use crate::solution::SolveResult;
+
+use super::rules::Rules;
+use super::Entry;
+
+// An exact fraction, always in lowest terms with a positive denominator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ratio {
+    num: i128,
+    den: i128,
+}
+
+// Unsigned, as the gcd of i128::MIN and 0 doesn't fit in an i128
+fn gcd(a: i128, b: i128) -> u128 {
+    let (mut a, mut b) = (a.unsigned_abs(), b.unsigned_abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+impl Ratio {
+    pub const ZERO: Ratio = Ratio { num: 0, den: 1 };
+    pub const ONE: Ratio = Ratio { num: 1, den: 1 };
+
+    // None if the denominator is 0, or the sign can't move onto i128::MIN
+    pub fn new(num: i128, den: i128) -> Option<Ratio> {
+        if den == 0 {
+            return None;
+        }
+        let (num, den) = match i128::try_from(gcd(num, den)) {
+            Ok(g) => (num / g, den / g),
+            // Only i128::MIN over itself, or 0 over it
+            Err(_) => (num.signum(), den.signum()),
+        };
+        if den < 0 {
+            return Some(Ratio { num: num.checked_neg()?, den: den.checked_neg()? });
+        }
+        Some(Ratio { num, den })
+    }
+
+    pub fn int(n: isize) -> Ratio {
+        Ratio { num: n as i128, den: 1 }
+    }
+
+    pub fn denom(&self) -> i128 {
+        self.den
+    }
+
+    pub fn checked_add(self, other: Ratio) -> Option<Ratio> {
+        let g = i128::try_from(gcd(self.den, other.den)).ok()?;
+        let num = self.num.checked_mul(other.den / g)?.checked_add(other.num.checked_mul(self.den / g)?)?;
+        Ratio::new(num, self.den.checked_mul(other.den / g)?)
+    }
+
+    pub fn checked_sub(self, other: Ratio) -> Option<Ratio> {
+        self.checked_add(Ratio { num: other.num.checked_neg()?, den: other.den })
+    }
+
+    pub fn checked_mul(self, other: Ratio) -> Option<Ratio> {
+        // Cancel across first, to keep the products small
+        let (a, b) = (i128::try_from(gcd(self.num, other.den)).ok()?.max(1), i128::try_from(gcd(other.num, self.den)).ok()?.max(1));
+        Ratio::new((self.num / a).checked_mul(other.num / b)?, (self.den / b).checked_mul(other.den / a)?)
+    }
+
+    pub fn checked_div(self, other: Ratio) -> Option<Ratio> {
+        if other.num == 0 {
+            return None;
+        }
+        // checked_mul puts the sign back on the denominator
+        self.checked_mul(Ratio { num: other.den, den: other.num })
+    }
+
+    pub fn is_positive(&self) -> bool {
+        self.num > 0
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.num < 0
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.num as f64 / self.den as f64
+    }
+
+    // "3", "1/3" or "0.25". Only the numerator may have a sign.
+    pub fn parse(text: &str) -> Option<Ratio> {
+        if let Some((num, den)) = text.split_once('/') {
+            if den.is_empty() || !den.bytes().all(|b| b.is_ascii_digit()) {
+                return None;
+            }
+            return Ratio::new(num.parse().ok()?, den.parse().ok()?);
+        }
+        if let Some((whole, frac)) = text.split_once('.') {
+            // The sign belongs to the whole number, as -0 alone would lose it
+            let (negative, whole) = match whole.strip_prefix('-') {
+                Some(whole) => (true, whole),
+                None => (false, whole),
+            };
+            let digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+            if frac.is_empty() || !digits(whole) || !digits(frac) || frac.len() > 30 {
+                return None;
+            }
+            let whole: i128 = if whole.is_empty() { 0 } else { whole.parse().ok()? };
+            let scale = 10i128.pow(frac.len() as u32);
+            let num = whole.checked_mul(scale)?.checked_add(frac.parse::<i128>().ok()?)?;
+            return Ratio::new(if negative { -num } else { num }, scale);
+        }
+        Ratio::new(text.parse().ok()?, 1)
+    }
+}
+
+impl Ord for Ratio {
+    fn cmp(&self, other: &Ratio) -> Ordering {
+        match (self.num.checked_mul(other.den), other.num.checked_mul(self.den)) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            // Too big to cross multiply; only a near tie could come out wrong
+            _ => self.to_f64().total_cmp(&other.to_f64()),
+        }
+    }
+}
+
+impl PartialOrd for Ratio {
+    fn partial_cmp(&self, other: &Ratio) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Ratio {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.den == 1 {
+            write!(f, "{}", self.num)
+        } else {
+            write!(f, "{}/{}", self.num, self.den)
+        }
+    }
+}
+
+// Exact arithmetic can overflow in big enough games; say so rather than
+// give a wrong answer
+fn exact<T>(res: Option<T>) -> SolveResult<T> {
+    res.ok_or_else(|| Error::solve("the fractions got too big to keep exact"))
+}
+
+fn sum(values: impl IntoIterator<Item = Ratio>) -> SolveResult<Ratio> {
+    values.into_iter().try_fold(Ratio::ZERO, |acc, value| exact(acc.checked_add(value)))
+}
+
+// Scales weights to sum to 1
+fn normalise(weights: Vec<Ratio>) -> SolveResult<Vec<Ratio>> {
+    let total = sum(weights.iter().copied())?;
+    if !total.is_positive() {
+        return Err(Error::solve("the opponent's weights add up to nothing"));
+    }
+    weights.into_iter().map(|weight| exact(weight.checked_div(total))).collect()
+}
+
+// The opponent plays every shape equally often
+pub fn uniform(rules: &Rules) -> Vec<Ratio> {
+    vec![Ratio::new(1, rules.shapes.len() as i128).unwrap_or(Ratio::ZERO); rules.shapes.len()]
+}
+
+// Weights by shape name, like "rock=2,paper=1,scissors=1/2". Shapes left
+// out are never played.
+pub fn parse_mix(rules: &Rules, spec: &str) -> SolveResult<Vec<Ratio>> {
+    let mut weights = vec![Ratio::ZERO; rules.shapes.len()];
+    for part in spec.split(',') {
+        let Some((name, weight)) = part.split_once('=') else {
+            return Err(Error::parse(spec, part, "expected SHAPE=WEIGHT"));
+        };
+        let Some(shape) = rules.shape(name) else {
+            let names: Vec<&str> = rules.shapes.iter().map(|shape| shape.name.as_str()).collect();
+            return Err(Error::parse(spec, name, format!("unknown shape '{}' (expected {})", name, names.join(", "))));
+        };
+        match Ratio::parse(weight) {
+            Some(weight) if !weight.is_negative() => weights[shape] = weight,
+            _ => return Err(Error::parse(spec, weight, format!("'{}' isn't a weight, like 2, 1/3 or 0.5", weight))),
+        }
+    }
+    normalise(weights)
+}
+
+// How often the guide's opponent plays each shape
+pub fn guide_mix(rules: &Rules, guide: &[Entry]) -> SolveResult<Vec<Ratio>> {
+    let mut counts = vec![0; rules.shapes.len()];
+    for (idx, instructions) in guide.iter().enumerate() {
+        match rules.opponent_shape(&instructions[0]) {
+            Some(shape) => counts[shape] += 1,
+            None => return Err(super::unknown(instructions, 0, &rules.opponent_codes()).at_line(idx + 1)),
+        }
+    }
+    normalise(counts.into_iter().map(Ratio::int).collect())
+}
+
+// Your expected score with each shape against the opponent's mix
+pub fn expected(rules: &Rules, opponent: &[Ratio]) -> SolveResult<Vec<Ratio>> {
+    let shapes = rules.shapes.len();
+    (0..shapes)
+        .map(|you| {
+            let scores = (0..shapes).map(|op| exact(opponent[op].checked_mul(Ratio::int(rules.score(you, op)))));
+            sum(scores.collect::<SolveResult<Vec<_>>>()?)
+        })
+        .collect()
+}
+
+// Every shape with the highest expected score; being exact, ties are real
+pub fn best_responses(expected: &[Ratio]) -> Vec<usize> {
+    let Some(best) = expected.iter().max() else { return Vec::new() };
+    (0..expected.len()).filter(|&shape| expected[shape] == *best).collect()
+}
+
+// A mix that nobody can outscore on average. Matches are won on the margin,
+// so this is the equilibrium of the zero sum game paying your score less
+// theirs; the game is symmetric, so its value is 0.
+pub fn equilibrium(rules: &Rules) -> SolveResult<Vec<Ratio>> {
+    let n = rules.shapes.len();
+    let margin = |you: usize, op: usize| rules.score(you, op) - rules.score(op, you);
+    // Shift the payoffs to at least 1 so the linear program below is
+    // feasible at 0 and bounded
+    let lowest = (0..n).flat_map(|you| (0..n).map(move |op| (you, op))).map(|(you, op)| margin(you, op)).min().unwrap_or(0);
+    let shift = 1 - lowest;
+
+    // The column player's program: maximise sum(u) with A u <= 1, u >= 0.
+    // Rows are [A | slacks | rhs], with the objective row last.
+    let width = 2 * n + 1;
+    let mut tableau = vec![vec![Ratio::ZERO; width]; n + 1];
+    for (you, row) in tableau.iter_mut().take(n).enumerate() {
+        for (op, cell) in row.iter_mut().take(n).enumerate() {
+            *cell = Ratio::int(margin(you, op) + shift);
+        }
+        row[n + you] = Ratio::ONE;
+        row[2 * n] = Ratio::ONE;
+    }
+    for cell in tableau[n].iter_mut().take(n) {
+        *cell = Ratio::int(-1);
+    }
+    let mut basis: Vec<usize> = (n..2 * n).collect();
+
+    // Bland's rule: lowest index in, lowest index out on ties, so it can't cycle
+    while let Some(enter) = (0..2 * n).find(|&col| tableau[n][col].is_negative()) {
+        let mut leave: Option<(usize, Ratio)> = None;
+        for row in 0..n {
+            if tableau[row][enter].is_positive() {
+                let ratio = exact(tableau[row][2 * n].checked_div(tableau[row][enter]))?;
+                let better = match leave {
+                    None => true,
+                    Some((best, best_ratio)) => ratio < best_ratio || (ratio == best_ratio && basis[row] < basis[best]),
+                };
+                if better {
+                    leave = Some((row, ratio));
+                }
+            }
+        }
+        // Can't happen with every payoff positive, but don't loop forever
+        let Some((pivot, _)) = leave else {
+            return Err(Error::solve("the game has no equilibrium"));
+        };
+        let scale = tableau[pivot][enter];
+        for cell in tableau[pivot].iter_mut() {
+            *cell = exact(cell.checked_div(scale))?;
+        }
+        let pivot_row = tableau[pivot].clone();
+        for (idx, row) in tableau.iter_mut().enumerate() {
+            let factor = row[enter];
+            if idx != pivot && factor != Ratio::ZERO {
+                for (cell, &by) in row.iter_mut().zip(&pivot_row) {
+                    *cell = exact(cell.checked_sub(exact(factor.checked_mul(by))?))?;
+                }
+            }
+        }
+        basis[pivot] = enter;
+    }
+
+    let mut weights = vec![Ratio::ZERO; n];
+    for (row, &var) in basis.iter().enumerate() {
+        if var < n {
+            weights[var] = tableau[row][2 * n];
+        }
+    }
+    normalise(weights)
+}
+
+// Your expected score playing one mix against another
+pub fn mean(rules: &Rules, you: &[Ratio], opponent: &[Ratio]) -> SolveResult<Ratio> {
+    let per_shape = expected(rules, opponent)?;
+    sum(you.iter().zip(per_shape).map(|(&p, score)| exact(p.checked_mul(score))).collect::<SolveResult<Vec<_>>>()?)
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Analysis {
+    pub opponent: Vec<Ratio>,
+    // Your expected score with each shape against `opponent`
+    pub expected: Vec<Ratio>,
+    pub best: Vec<usize>,
+    pub equilibrium: Vec<Ratio>,
+    // What the equilibrium scores against itself, and against `opponent`
+    pub equilibrium_mirror: Ratio,
+    pub equilibrium_score: Ratio,
+}
+
+pub fn analyse(rules: &Rules, opponent: Vec<Ratio>) -> SolveResult<Analysis> {
+    let expected = expected(rules, &opponent)?;
+    let best = best_responses(&expected);
+    let equilibrium = equilibrium(rules)?;
+    let equilibrium_mirror = mean(rules, &equilibrium, &equilibrium)?;
+    let equilibrium_score = mean(rules, &equilibrium, &opponent)?;
+    Ok(Analysis { opponent, expected, best, equilibrium, equilibrium_mirror, equilibrium_score })
+}
+
+// Fractions are exact; the decimals beside them are rounded to 4 places
+fn show(value: &Ratio) -> String {
+    if value.denom() == 1 {
+        value.to_string()
+    } else {
+        format!("{} (~{:.4})", value, value.to_f64())
+    }
+}
+
+impl Analysis {
+    pub fn text(&self, rules: &Rules) -> String {
+        let width = rules.shapes.iter().map(|shape| shape.name.chars().count()).max().unwrap_or(0).max("Shape".len());
+        let mut res = String::new();
+        writeln!(res, "{:<width$}  {:<20}  {:<20}  {:<20}", "Shape", "Opponent plays", "Your expected score", "Equilibrium", width = width).unwrap();
+        for (shape, rule) in rules.shapes.iter().enumerate() {
+            let best = if self.best.contains(&shape) { "  best" } else { "" };
+            writeln!(
+                res,
+                "{:<width$}  {:<20}  {:<20}  {:<20}{}",
+                rule.name, show(&self.opponent[shape]), show(&self.expected[shape]), show(&self.equilibrium[shape]), best,
+                width = width
+            ).unwrap();
+        }
+        let names: Vec<&str> = self.best.iter().map(|&shape| rules.shapes[shape].name.as_str()).collect();
+        writeln!(res).unwrap();
+        writeln!(res, "Best response: {}, scoring {} a round", names.join(" or "), show(&self.expected[self.best[0]])).unwrap();
+        writeln!(res, "Equilibrium scores {} a round against this opponent", show(&self.equilibrium_score)).unwrap();
+        writeln!(res, "Equilibrium scores {} a round against itself", show(&self.equilibrium_mirror)).unwrap();
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ratio(num: i128, den: i128) -> Ratio {
+        Ratio::new(num, den).unwrap()
+    }
+
+    #[test]
+    fn ratios() {
+        assert_eq!(ratio(1, 2), ratio(-3, -6));
+        assert_eq!(Some(ratio(5, 6)), ratio(1, 2).checked_add(ratio(1, 3)));
+        assert_eq!(Some(ratio(-1, 6)), ratio(1, 6).checked_sub(ratio(1, 3)));
+        assert_eq!(Some(ratio(3, 2)), ratio(1, 2).checked_div(ratio(1, 3)));
+        assert_eq!(None, Ratio::ONE.checked_div(Ratio::ZERO));
+        assert_eq!(None, Ratio::int(isize::MAX).checked_mul(Ratio::new(i128::MAX, 1).unwrap()));
+        assert!(ratio(2, 3) > ratio(3, 5));
+        assert_eq!(vec![Some(ratio(1, 4)), Some(Ratio::int(3)), Some(ratio(2, 3)), None, None],
+                   ["0.25", "3", "4/6", "1/0", "x"].map(Ratio::parse).to_vec());
+        assert_eq!(vec![Some(ratio(-3, 2)), Some(ratio(-1, 2)), Some(ratio(1, 2)), Some(ratio(-1, 2)), None, None],
+                   ["-1.5", "-0.5", ".5", "-.5", "--0.5", "1.-5"].map(Ratio::parse).to_vec());
+        assert_eq!("-7/18", ratio(7, -18).to_string());
+
+        // i128::MIN can't change sign, but can be reduced
+        assert_eq!(None, Ratio::new(i128::MIN, -1));
+        assert_eq!(Some(Ratio::ONE), Ratio::new(i128::MIN, i128::MIN));
+        assert_eq!(Some(Ratio::ZERO), Ratio::new(0, i128::MIN));
+        assert_eq!(Some(Ratio { num: i128::MIN / 2, den: 1 }), Ratio::new(i128::MIN, 2));
+        assert_eq!(None, Ratio::ZERO.checked_sub(ratio(i128::MIN, 1)));
+        assert_eq!(None, Ratio::parse("-170141183460469231731687303715884105728/-1"));
+        assert_eq!(None, Ratio::parse("-1/-1"));
+    }
+
+    #[test]
+    fn classic() {
+        let rules = Rules::classic();
+        // Against a uniform opponent outcomes average 3, so the shape score decides
+        let res = analyse(&rules, uniform(&rules)).unwrap();
+        assert_eq!(vec![Ratio::int(4), Ratio::int(5), Ratio::int(6)], res.expected);
+        assert_eq!(vec![2], res.best);
+
+        // Paper wins by 7 either way, rock beats scissors by only 4, so the
+        // equilibrium plays paper least
+        assert_eq!(vec![ratio(7, 18), ratio(2, 9), ratio(7, 18)], res.equilibrium);
+        assert_eq!(Ratio::int(5), res.equilibrium_mirror);
+        assert_eq!(Ratio::int(5), res.equilibrium_score);
+
+        // Against all rock, paper is the only answer
+        let res = analyse(&rules, parse_mix(&rules, "rock=1").unwrap()).unwrap();
+        assert_eq!((vec![1], Ratio::int(8)), (res.best.clone(), res.expected[1]));
+        assert!(res.text(&rules).contains("Best response: paper, scoring 8 a round"));
+    }
+
+    #[test]
+    fn wide_names() {
+        let rules = Rules::parse("shape piérre 1\nshape papier 2\npapier beats piérre\n").unwrap();
+        let text = analyse(&rules, uniform(&rules)).unwrap().text(&rules);
+        assert!(text.contains("\npiérre  1/2 "), "{}", text);
+    }
+
+    #[test]
+    fn equilibria() {
+        // Every shape scoring the same leaves the textbook thirds, and a
+        // fifth each in rock paper scissors lizard Spock
+        let rules = Rules::parse("shape rock 1\nshape paper 1\nshape scissors 1\nrock beats scissors\npaper beats rock\nscissors beats paper\n").unwrap();
+        assert_eq!(vec![ratio(1, 3); 3], equilibrium(&rules).unwrap());
+        let rules = Rules::parse("shape a 0\nshape b 0\nshape c 0\nshape d 0\nshape e 0\na beats c d\nb beats a e\nc beats b d\nd beats b e\ne beats a c\n").unwrap();
+        assert_eq!(vec![ratio(1, 5); 5], equilibrium(&rules).unwrap());
+
+        // Nothing can beat an equilibrium on average
+        for rules in [Rules::classic(), Rules::load("rpsls").unwrap()] {
+            let mix = equilibrium(&rules).unwrap();
+            let n = rules.shapes.len();
+            for op in 0..n {
+                let margin: Vec<Ratio> = (0..n).map(|you| {
+                    Ratio::int(rules.score(you, op) - rules.score(op, you)).checked_mul(mix[you]).unwrap()
+                }).collect();
+                assert!(sum(margin).unwrap() >= Ratio::ZERO, "{} beats the equilibrium", rules.shapes[op].name);
+            }
+        }
+    }
+
+    #[test]
+    fn mixes() {
+        let rules = Rules::classic();
+        assert_eq!(vec![ratio(1, 2), Ratio::ZERO, ratio(1, 2)], parse_mix(&rules, "rock=0.5,scissors=1/2").unwrap());
+        assert_eq!(vec![ratio(1, 3), ratio(2, 3), Ratio::ZERO], parse_mix(&rules, "rock=0.5,paper=1").unwrap());
+        for spec in ["rock", "rock=x", "lizard=1", "rock=-1", "rock=0"] {
+            assert!(parse_mix(&rules, spec).is_err(), "'{}' should not parse", spec);
+        }
+        // Negative decimals point at the weight
+        for (spec, column) in [("rock=-0.5,paper=1", 6), ("rock=-1.5,paper=2", 6), ("paper=1,rock=-0.5", 14)] {
+            match parse_mix(&rules, spec) {
+                Err(Error::Parse(err)) => assert_eq!(column, err.column, "{}", spec),
+                other => panic!("expected a parse error for '{}', got {:?}", spec, other),
+            }
+        }
+        let guide = [["A".to_string(), "Y".to_string()], ["A".to_string(), "X".to_string()], ["C".to_string(), "Z".to_string()]];
+        assert_eq!(vec![ratio(2, 3), Ratio::ZERO, ratio(1, 3)], guide_mix(&rules, &guide).unwrap());
+    }
+}
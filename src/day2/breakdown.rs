@@ -0,0 +1,234 @@
+use std::fmt::Write as _;
+
+use crate::solution::SolveResult;
+
+use super::rules::{Outcome, Rules, OUTCOMES};
+use super::{parse_line, parse_line_part2, Entry, Round};
+
+// One round of the guide, as a part scores it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step {
+    pub opponent: usize,
+    pub you: usize,
+    pub outcome: Outcome,
+    pub shape_score: isize,
+    pub outcome_score: isize,
+    // Running total, this round included
+    pub total: isize,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Breakdown {
+    pub part: usize,
+    pub steps: Vec<Step>,
+    // Rounds lost, drawn and won, in the order of `OUTCOMES`
+    pub outcomes: [usize; 3],
+    // How often each shape was played, by position in the rules
+    pub you_played: Vec<usize>,
+    pub opponent_played: Vec<usize>,
+}
+
+// Every round of the guide as part 1 or 2 reads it, falling back on unknown
+// codes the same way unless `strict`
+pub fn breakdown(rules: &Rules, strict: bool, part: usize, guide: &[Entry]) -> SolveResult<Breakdown> {
+    let read = if part == 1 { parse_line } else { parse_line_part2 };
+    let shapes = rules.shapes.len();
+    let mut res = Breakdown {
+        part,
+        steps: Vec::with_capacity(guide.len()),
+        outcomes: [0; 3],
+        you_played: vec![0; shapes],
+        opponent_played: vec![0; shapes],
+    };
+    let mut total = 0;
+    for (idx, instructions) in guide.iter().enumerate() {
+        let Round { you, opponent } = read(rules, strict, instructions).map_err(|err| err.at_line(idx + 1))?;
+        let outcome = rules.outcome(you.0, opponent.0);
+        let (shape_score, outcome_score) = (rules.shapes[you.0].score, rules.outcome_score(outcome));
+        total += shape_score + outcome_score;
+        res.outcomes[outcome as usize] += 1;
+        res.you_played[you.0] += 1;
+        res.opponent_played[opponent.0] += 1;
+        res.steps.push(Step { opponent: opponent.0, you: you.0, outcome, shape_score, outcome_score, total });
+    }
+    Ok(res)
+}
+
+// Shape names come from rules files, so may need quoting
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+fn json_string(text: &str) -> String {
+    let mut res = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => res.push_str("\\\""),
+            '\\' => res.push_str("\\\\"),
+            c if (c as u32) < 0x20 => write!(res, "\\u{:04x}", c as u32).unwrap(),
+            c => res.push(c),
+        }
+    }
+    res.push('"');
+    res
+}
+
+impl Breakdown {
+    pub fn total(&self) -> isize {
+        self.steps.last().map_or(0, |step| step.total)
+    }
+
+    pub fn text(&self, rules: &Rules) -> String {
+        let width = rules.shapes.iter().map(|shape| shape.name.chars().count()).max().unwrap_or(0).max("Opponent".len());
+        let mut res = String::new();
+        writeln!(
+            res,
+            "Round  {:<width$}  {:<width$}  Outcome  Shape  Result  {:>8}",
+            "Opponent", "You", "Total", width = width
+        ).unwrap();
+        for (idx, step) in self.steps.iter().enumerate() {
+            writeln!(
+                res,
+                "{:>5}  {:<width$}  {:<width$}  {:<7}  {:>5}  {:>6}  {:>8}",
+                idx + 1, rules.shapes[step.opponent].name, rules.shapes[step.you].name, step.outcome.name(),
+                step.shape_score, step.outcome_score, step.total,
+                width = width
+            ).unwrap();
+        }
+        writeln!(res).unwrap();
+        writeln!(res, "Part {} total: {} over {} rounds", self.part, self.total(), self.steps.len()).unwrap();
+        let outcomes: Vec<String> = OUTCOMES.iter().map(|outcome| format!("{} {}", self.outcomes[*outcome as usize], outcome.name())).collect();
+        writeln!(res, "Outcomes: {}", outcomes.join(", ")).unwrap();
+        writeln!(res, "{:<width$}  {:>8}  {:>8}", "Shape", "You", "Opponent", width = width).unwrap();
+        for (shape, (you, opponent)) in rules.shapes.iter().zip(self.you_played.iter().zip(&self.opponent_played)) {
+            writeln!(res, "{:<width$}  {:>8}  {:>8}", shape.name, you, opponent, width = width).unwrap();
+        }
+        res
+    }
+
+    // One row per round; the outcome and shape counts are only in the text
+    // and JSON reports
+    pub fn csv(&self, rules: &Rules) -> String {
+        let mut res = String::from("round,opponent,you,outcome,shape_score,outcome_score,score,total\n");
+        for (idx, step) in self.steps.iter().enumerate() {
+            writeln!(
+                res,
+                "{},{},{},{},{},{},{},{}",
+                idx + 1, csv_field(&rules.shapes[step.opponent].name), csv_field(&rules.shapes[step.you].name),
+                step.outcome.name(), step.shape_score, step.outcome_score, step.shape_score + step.outcome_score, step.total
+            ).unwrap();
+        }
+        res
+    }
+
+    pub fn json(&self, rules: &Rules) -> String {
+        let counts = |played: &[usize]| -> String {
+            let counts: Vec<String> = rules.shapes
+                .iter()
+                .zip(played)
+                .map(|(shape, count)| format!("{}: {}", json_string(&shape.name), count))
+                .collect();
+            format!("{{{}}}", counts.join(", "))
+        };
+        let outcomes: Vec<String> = OUTCOMES.iter().map(|outcome| format!("\"{}\": {}", outcome.name(), self.outcomes[*outcome as usize])).collect();
+        let mut res = String::from("{\n");
+        writeln!(res, "  \"part\": {},", self.part).unwrap();
+        writeln!(res, "  \"total\": {},", self.total()).unwrap();
+        writeln!(res, "  \"outcomes\": {{{}}},", outcomes.join(", ")).unwrap();
+        writeln!(res, "  \"shapes\": {{\"you\": {}, \"opponent\": {}}},", counts(&self.you_played), counts(&self.opponent_played)).unwrap();
+        res.push_str("  \"rounds\": [");
+        for (idx, step) in self.steps.iter().enumerate() {
+            if idx > 0 {
+                res.push(',');
+            }
+            write!(
+                res,
+                "\n    {{\"round\": {}, \"opponent\": {}, \"you\": {}, \"outcome\": \"{}\", \"shape_score\": {}, \"outcome_score\": {}, \"score\": {}, \"total\": {}}}",
+                idx + 1, json_string(&rules.shapes[step.opponent].name), json_string(&rules.shapes[step.you].name),
+                step.outcome.name(), step.shape_score, step.outcome_score, step.shape_score + step.outcome_score, step.total
+            ).unwrap();
+        }
+        res.push_str("\n  ]\n}\n");
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day2::{parse_entry, EXAMPLES};
+    use crate::error::Error;
+    use crate::util::{parse_lines, split_lines};
+
+    fn guide(input: &str) -> Vec<Entry> {
+        parse_lines(&split_lines(input), parse_entry).unwrap()
+    }
+
+    #[test]
+    fn example_breakdown() {
+        let rules = Rules::classic();
+        let guide = guide(EXAMPLES[0].input);
+        let res = breakdown(&rules, true, 1, &guide).unwrap();
+        let totals: Vec<isize> = res.steps.iter().map(|step| step.total).collect();
+        assert_eq!(vec![8, 9, 15], totals);
+        assert_eq!(super::super::part1(&rules, true, &guide).unwrap(), res.total());
+        assert_eq!([1, 1, 1], res.outcomes);
+        assert_eq!((vec![1, 1, 1], vec![1, 1, 1]), (res.you_played.clone(), res.opponent_played.clone()));
+        assert_eq!(Step { opponent: 0, you: 1, outcome: Outcome::Win, shape_score: 2, outcome_score: 6, total: 8 }, res.steps[0]);
+
+        // Part 2 plays rock three times
+        let res = breakdown(&rules, true, 2, &guide).unwrap();
+        assert_eq!(12, res.total());
+        assert_eq!(vec![3, 0, 0], res.you_played);
+    }
+
+    #[test]
+    fn exports() {
+        let rules = Rules::parse("shape \"odd,name\" 1\nshape paper 2\npaper beats \"odd,name\"\n").unwrap();
+        let res = breakdown(&rules, true, 1, &guide("A Y\nB Z\n")).unwrap();
+        let csv = res.csv(&rules);
+        assert_eq!(
+            "round,opponent,you,outcome,shape_score,outcome_score,score,total\n\
+             1,\"\"\"odd,name\"\"\",\"\"\"odd,name\"\"\",draw,1,3,4,4\n\
+             2,paper,paper,draw,2,3,5,9\n",
+            csv
+        );
+        let json = res.json(&rules);
+        assert!(json.contains("\"shapes\": {\"you\": {\"\\\"odd,name\\\"\": 1, \"paper\": 1}"), "{}", json);
+        assert!(
+            json.contains("{\"round\": 2, \"opponent\": \"paper\", \"you\": \"paper\", \"outcome\": \"draw\", \"shape_score\": 2, \"outcome_score\": 3, \"score\": 5, \"total\": 9}"),
+            "{}", json
+        );
+        // Each round carries the same fields as a CSV row
+        let header = csv.lines().next().unwrap();
+        let keys: Vec<String> = header.split(',').map(|key| format!("\"{}\": ", key)).collect();
+        let rounds: Vec<&str> = json.lines().filter(|line| line.trim_start().starts_with("{\"round\"")).collect();
+        assert_eq!(2, rounds.len(), "{}", json);
+        for round in rounds {
+            assert!(keys.iter().all(|key| round.contains(key.as_str())), "{}", round);
+        }
+        assert!(res.text(&rules).contains("Outcomes: 0 loss, 2 draw, 0 win"));
+    }
+
+    #[test]
+    fn wide_names() {
+        let rules = Rules::parse("shape pierre-à-feu 1\nshape papier 2\npapier beats pierre-à-feu\n").unwrap();
+        let text = breakdown(&rules, true, 1, &guide("A Y\nB Z\n")).unwrap().text(&rules);
+        assert!(text.contains("    1  pierre-à-feu  pierre-à-feu  draw"), "{}", text);
+    }
+
+    #[test]
+    fn strict_breakdown() {
+        let rules = Rules::classic();
+        let guide = guide("A Y\nB Q\n");
+        assert_eq!(8 + 9, breakdown(&rules, false, 1, &guide).unwrap().total());
+        match breakdown(&rules, true, 2, &guide) {
+            Err(Error::Parse(err)) => assert_eq!(Some(2), err.line),
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,235 @@
+use std::fmt::Write as _;
+
+use crate::error::Error;
+use crate::solution::SolveResult;
+
+use super::rules::{Outcome, Rules};
+use super::{unknown, Entry, Hand, Round};
+
+// Every way of reading the guide's second column: as shapes, with its codes
+// assigned to shapes in every possible way, or as the outcome you need
+#[derive(Debug, Clone, PartialEq)]
+pub enum Reading {
+    // The shape for each code, in the order of `Interpretations::codes`
+    Shapes(Vec<usize>),
+    Outcomes,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Interpretation {
+    pub reading: Reading,
+    pub score: isize,
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Interpretations {
+    // The codes seen in the second column: the rules' own first, in order
+    pub codes: Vec<String>,
+    // Highest score first; ties keep the order the readings were tried in
+    pub ranked: Vec<Interpretation>,
+    // Why the outcome reading couldn't be scored, if it couldn't
+    pub no_outcomes: Option<String>,
+}
+
+// Shape readings tried before giving up; 8 shapes is 40,320 of them
+const MAX_READINGS: usize = 40_320;
+
+pub fn interpret(rules: &Rules, guide: &[Entry]) -> SolveResult<Interpretations> {
+    let mut opponents = Vec::with_capacity(guide.len());
+    for (idx, instructions) in guide.iter().enumerate() {
+        match rules.opponent_shape(&instructions[0]) {
+            Some(shape) => opponents.push(Hand(shape)),
+            None => return Err(unknown(instructions, 0, &rules.opponent_codes()).at_line(idx + 1)),
+        }
+    }
+
+    let mut codes: Vec<String> = Vec::new();
+    for instructions in guide {
+        if !codes.contains(&instructions[1]) {
+            codes.push(instructions[1].clone());
+        }
+    }
+    codes.sort_by_key(|code| (rules.your_shape(code).is_none(), rules.your_shape(code), code.clone()));
+    let column: Vec<usize> = guide.iter().map(|instructions| codes.iter().position(|code| *code == instructions[1]).unwrap_or(0)).collect();
+
+    let shapes = rules.shapes.len();
+    if codes.len() > shapes {
+        return Err(Error::solve(format!(
+            "the second column has {} different codes, more than the {} shapes they could stand for",
+            codes.len(), shapes
+        )));
+    }
+    let readings: usize = (shapes - codes.len() + 1..=shapes).try_fold(1usize, |acc, n| acc.checked_mul(n)).unwrap_or(usize::MAX);
+    if readings > MAX_READINGS {
+        return Err(Error::solve(format!("there are {} ways to read the second column as shapes, too many to try", readings)));
+    }
+
+    let mut ranked = Vec::new();
+    for assignment in assignments(codes.len(), shapes) {
+        let mut res = Interpretation { reading: Reading::Shapes(assignment.clone()), score: 0, wins: 0, draws: 0, losses: 0 };
+        for (&opponent, &code) in opponents.iter().zip(&column) {
+            res.add(rules, &Round { you: Hand(assignment[code]), opponent });
+        }
+        ranked.push(res);
+    }
+
+    let mut no_outcomes = None;
+    match codes.iter().find(|code| rules.needed(code).is_none()) {
+        Some(code) => no_outcomes = Some(format!("'{}' isn't an outcome code", code)),
+        None => {
+            let mut res = Interpretation { reading: Reading::Outcomes, score: 0, wins: 0, draws: 0, losses: 0 };
+            for (idx, (&opponent, instructions)) in opponents.iter().zip(guide).enumerate() {
+                let goal = rules.needed(&instructions[1]).unwrap_or(Outcome::Loss);
+                match Round::cheat(rules, opponent, goal) {
+                    Ok(round) => res.add(rules, &round),
+                    Err(err) => {
+                        no_outcomes = Some(format!("line {}: {}", idx + 1, err));
+                        break;
+                    }
+                }
+            }
+            if no_outcomes.is_none() {
+                ranked.push(res);
+            }
+        }
+    }
+
+    // Stable, so equal scores stay in the order they were tried
+    ranked.sort_by_key(|res| -res.score);
+    Ok(Interpretations { codes, ranked, no_outcomes })
+}
+
+// Every way to give `codes` codes different shapes out of `shapes`, in
+// lexicographic order
+fn assignments(codes: usize, shapes: usize) -> Vec<Vec<usize>> {
+    fn extend(current: &mut Vec<usize>, codes: usize, shapes: usize, res: &mut Vec<Vec<usize>>) {
+        if current.len() == codes {
+            res.push(current.clone());
+            return;
+        }
+        for shape in 0..shapes {
+            if !current.contains(&shape) {
+                current.push(shape);
+                extend(current, codes, shapes, res);
+                current.pop();
+            }
+        }
+    }
+    let mut res = Vec::new();
+    extend(&mut Vec::with_capacity(codes), codes, shapes, &mut res);
+    res
+}
+
+impl Interpretation {
+    fn add(&mut self, rules: &Rules, round: &Round) {
+        self.score += round.score(rules);
+        match round.result(rules) {
+            Outcome::Win => self.wins += 1,
+            Outcome::Draw => self.draws += 1,
+            Outcome::Loss => self.losses += 1,
+        }
+    }
+}
+
+impl Interpretations {
+    // "X=rock Y=paper Z=scissors", or "X=loss Y=draw Z=win"
+    pub fn label(&self, rules: &Rules, reading: &Reading) -> String {
+        let names: Vec<String> = match reading {
+            Reading::Shapes(assignment) => self.codes
+                .iter()
+                .zip(assignment)
+                .map(|(code, &shape)| format!("{}={}", code, rules.shapes[shape].name))
+                .collect(),
+            Reading::Outcomes => self.codes
+                .iter()
+                .map(|code| format!("{}={}", code, rules.needed(code).map_or("?", |outcome| outcome.name())))
+                .collect(),
+        };
+        names.join(" ")
+    }
+
+    // Which reading each part of the puzzle uses
+    fn part(&self, rules: &Rules, reading: &Reading) -> Option<usize> {
+        match reading {
+            Reading::Shapes(assignment) => {
+                let part1 = self.codes.iter().zip(assignment).all(|(code, &shape)| rules.your_shape(code) == Some(shape));
+                if part1 { Some(1) } else { None }
+            }
+            Reading::Outcomes => Some(2),
+        }
+    }
+
+    pub fn text(&self, rules: &Rules) -> String {
+        let labels: Vec<String> = self.ranked.iter().map(|res| self.label(rules, &res.reading)).collect();
+        let width = labels.iter().map(|label| label.chars().count()).max().unwrap_or(0).max("Reading".len());
+        let mut res = String::new();
+        writeln!(res, "Rank  {:<width$}  {:>8}  {:>5}  {:>5}  {:>6}", "Reading", "Score", "Wins", "Draws", "Losses", width = width).unwrap();
+        for (rank, (interpretation, label)) in self.ranked.iter().zip(&labels).enumerate() {
+            let part = match self.part(rules, &interpretation.reading) {
+                Some(part) => format!("  (part {})", part),
+                None => String::new(),
+            };
+            writeln!(
+                res,
+                "{:>4}  {:<width$}  {:>8}  {:>5}  {:>5}  {:>6}{}",
+                rank + 1, label, interpretation.score, interpretation.wins, interpretation.draws, interpretation.losses, part,
+                width = width
+            ).unwrap();
+        }
+        if let Some(reason) = &self.no_outcomes {
+            writeln!(res, "Not read as outcomes: {}", reason).unwrap();
+        }
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day2::{parse_entry, EXAMPLES};
+    use crate::util::{parse_lines, split_lines};
+
+    fn guide(input: &str) -> Vec<Entry> {
+        parse_lines(&split_lines(input), parse_entry).unwrap()
+    }
+
+    #[test]
+    fn example_readings() {
+        let rules = Rules::classic();
+        let res = interpret(&rules, &guide(EXAMPLES[0].input)).unwrap();
+        assert_eq!(vec!["X", "Y", "Z"], res.codes);
+        assert_eq!(7, res.ranked.len());
+        assert_eq!(None, res.no_outcomes);
+
+        let find = |reading: &Reading| res.ranked.iter().find(|other| other.reading == *reading).unwrap();
+        // The puzzle's two readings give its two answers
+        let part1 = find(&Reading::Shapes(vec![0, 1, 2]));
+        assert_eq!((15, 1, 1, 1), (part1.score, part1.wins, part1.draws, part1.losses));
+        let part2 = find(&Reading::Outcomes);
+        assert_eq!((12, 1, 1, 1), (part2.score, part2.wins, part2.draws, part2.losses));
+
+        // Winning every round scores 8 + 9 + 7
+        assert_eq!(24, res.ranked[0].score);
+        assert_eq!("X=scissors Y=paper Z=rock", res.label(&rules, &res.ranked[0].reading));
+        assert!(res.text(&rules).contains("X=rock Y=paper Z=scissors        15      1      1       1  (part 1)"));
+    }
+
+    #[test]
+    fn odd_guides() {
+        let rules = Rules::classic();
+        // Two codes leave one shape unused, and Q can't be an outcome
+        let res = interpret(&rules, &guide("A Q\nB X\n")).unwrap();
+        assert_eq!(vec!["X", "Q"], res.codes);
+        assert_eq!(6, res.ranked.len());
+        assert!(res.no_outcomes.is_some());
+
+        assert!(interpret(&rules, &guide("A W\nB X\nC Y\nA Z\n")).is_err());
+        match interpret(&rules, &guide("A X\nD X\n")) {
+            Err(Error::Parse(err)) => assert_eq!(Some(2), err.line),
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
+}
@@ -1,7 +1,16 @@
-use crate::util::{read_lines, get_filename};
-use std::time::{Instant, Duration};
+use crate::error::Error;
+use crate::solution::{examples, Answer, Example, Solution, SolveResult};
+use crate::util::{parse_lines, split_lines};
 
-use super::Data;
+use rules::{Outcome, Rules};
+
+pub mod analysis;
+pub mod breakdown;
+pub mod interpret;
+pub mod rules;
+pub mod tournament;
+
+pub struct Day2;
 
 // Score for a single round:
 // Score for shape - Rock, Paper, Scissors = 1, 2, 3
@@ -11,25 +20,33 @@ use super::Data;
 // Opponent - A, B, C for RPS
 // You - XYZ for RPS
 
-#[derive(Debug, PartialEq)]
-enum Hand {
-    Rock,
-    Paper,
-    Scissors
+// A shape, by its position in the rules
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Hand(usize);
+
+// A code from `column` (0 for the opponent's, 1 for yours), or failing that
+// from the other column. Anything else falls back to the last shape, which
+// for the classic rules is scissors.
+fn letter_to_hand(rules: &Rules, column: usize, letter: &str) -> Hand {
+    let (own, other) = match column {
+        0 => (rules.opponent_shape(letter), rules.your_shape(letter)),
+        _ => (rules.your_shape(letter), rules.opponent_shape(letter)),
+    };
+    Hand(own.or(other).unwrap_or(rules.shapes.len() - 1))
 }
 
-enum Result {
-    Loss,
-    Draw,
-    Win
+// The guide line an entry came from, for pointing at a bad code
+fn entry_line(instructions: &Entry) -> String {
+    instructions.join(" ")
 }
 
-fn letter_to_hand(letter: &str) -> Hand {
-    match letter {
-        "A" | "X" => Hand::Rock,
-        "B" | "Y" => Hand::Paper,
-        _ => Hand::Scissors
-    }
+// In strict mode an unknown code is an error rather than a fallback
+fn unknown(instructions: &Entry, column: usize, expected: &[&str]) -> Error {
+    let line = entry_line(instructions);
+    let start = if column == 0 { 0 } else { instructions[0].len() + 1 };
+    let code = &instructions[column];
+    let message = format!("unknown code '{}' (expected {})", code, expected.join(", "));
+    Error::parse(&line, &line[start..start + code.len()], message)
 }
 
 #[derive(Debug, PartialEq)]
@@ -38,191 +55,208 @@ struct Round {
     opponent: Hand
 }
 impl Round {
-    fn cheat(op: Hand, res: Result) -> Round{
-        match op {
-            Hand::Rock => {
-                match res {
-                    Result::Win => Round{
-                        opponent: op,
-                        you: Hand::Paper
-                    },
-                    Result::Loss => Round{
-                        opponent: op,
-                        you: Hand::Scissors
-                    },
-                    Result::Draw => Round{
-                        opponent: op,
-                        you: Hand::Rock
-                    }
-                }
-            }
-            Hand::Paper => {
-                match res {
-                    Result::Win => Round{
-                        opponent: op,
-                        you: Hand::Scissors
-                    },
-                    Result::Loss => Round{
-                        opponent: op,
-                        you: Hand::Rock
-                    },
-                    Result::Draw =>Round{
-                        opponent: op,
-                        you: Hand::Paper
-                    }
-                }
-            }
-            Hand::Scissors => {
-                match res {
-                    Result::Win => Round{
-                        opponent: op,
-                        you: Hand::Rock
-                    },
-                    Result::Loss => Round{
-                        opponent: op,
-                        you: Hand::Paper
-                    },
-                    Result::Draw => Round{
-                        opponent: op,
-                        you: Hand::Scissors
-                    }
-                }
-            }
+    // The round where you get `res` against `op`
+    fn cheat(rules: &Rules, op: Hand, res: Outcome) -> SolveResult<Round> {
+        match rules.respond(op.0, res) {
+            Some(you) => Ok(Round { you: Hand(you), opponent: op }),
+            None => Err(Error::solve(format!("no shape gets a {} against {}", res.name(), rules.shapes[op.0].name))),
         }
     }
 
-    fn result(&self) -> Result {
-        match self.you {
-            Hand::Rock => {
-                match self.opponent {
-                    Hand::Rock => Result::Draw,
-                    Hand::Paper => Result::Loss,
-                    Hand::Scissors => Result::Win
-                }
-            }
-            Hand::Paper => {
-                match self.opponent {
-                    Hand::Rock => Result::Win,
-                    Hand::Paper => Result::Draw,
-                    Hand::Scissors => Result::Loss
-                }
-            }
-            Hand::Scissors => {
-                match self.opponent {
-                    Hand::Rock => Result::Loss,
-                    Hand::Paper => Result::Win,
-                    Hand::Scissors => Result::Draw
-                }
-            }
-        }
-
+    fn result(&self, rules: &Rules) -> Outcome {
+        rules.outcome(self.you.0, self.opponent.0)
     }
 
-    fn shape(&self) -> isize {
-        match self.you {
-            Hand::Rock => 1,
-            Hand::Paper => 2,
-            Hand::Scissors => 3
-        }
+    fn score(&self, rules: &Rules) -> isize {
+        rules.score(self.you.0, self.opponent.0)
     }
+}
 
-    fn score(&self) -> isize {
-        let mut res = 0;
-        res += self.shape();
-        match self.result() {
-            Result::Win => { res += 6;}
-            Result::Loss => {res += 0;}
-            Result::Draw => {res += 3;}
-        }
-        res
+// A guide line is two single-space separated columns
+fn columns(line: &str) -> SolveResult<[&str; 2]> {
+    let instructions: Vec<&str> = line.split(' ').collect();
+    match instructions[..] {
+        [op, you] if !op.is_empty() && !you.is_empty() => Ok([op, you]),
+        [_, _, extra, ..] => Err(Error::parse(line, extra, "expected two columns, found more")),
+        _ => Err(Error::parse(line, &line[line.len()..], "expected two columns")),
     }
 }
 
-fn parse_line(line: String) -> Round {
-    let instructions: Vec<&str> = line.split(" ").collect();
-    Round {
-        you: letter_to_hand(instructions[1]),
-        opponent: letter_to_hand(instructions[0])
-    }
+// The two columns of a guide line; what the second one means depends on the part
+pub type Entry = [String; 2];
+
+pub fn parse_entry(line: &str) -> SolveResult<Entry> {
+    Ok(columns(line)?.map(String::from))
 }
 
-fn parse_line_part2(line: String) -> isize {
-    let instructions: Vec<&str> = line.split(" ").collect();
-    let op: Hand;
-    match instructions[0]{
-        "A" => {op = Hand::Rock;}
-        "B" => {op = Hand::Paper;}
-        "C" => {op = Hand::Scissors;}
-        _ => {op = Hand::Rock;}
-    }
-    let goal: Result;
-    match instructions[1] {
-        "X" => {goal = Result::Loss;}
-        "Y" => {goal = Result::Draw;}
-        "Z" => {goal = Result::Win;}
-        _ => {goal = Result::Loss;}
+fn parse_line(rules: &Rules, strict: bool, instructions: &Entry) -> SolveResult<Round> {
+    if !strict {
+        return Ok(Round {
+            you: letter_to_hand(rules, 1, &instructions[1]),
+            opponent: letter_to_hand(rules, 0, &instructions[0])
+        });
     }
+    let opponent = rules.opponent_shape(&instructions[0]).ok_or_else(|| unknown(instructions, 0, &rules.opponent_codes()))?;
+    let you = rules.your_shape(&instructions[1]).ok_or_else(|| unknown(instructions, 1, &rules.your_codes()))?;
+    Ok(Round { you: Hand(you), opponent: Hand(opponent) })
+}
 
-    Round::cheat(op, goal).score()
+fn parse_line_part2(rules: &Rules, strict: bool, instructions: &Entry) -> SolveResult<Round> {
+    let op = match rules.opponent_shape(&instructions[0]) {
+        Some(shape) => Hand(shape),
+        None if strict => return Err(unknown(instructions, 0, &rules.opponent_codes())),
+        None => Hand(0),
+    };
+    let goal = match rules.needed(&instructions[1]) {
+        Some(outcome) => outcome,
+        None if strict => return Err(unknown(instructions, 1, &rules.need_codes())),
+        None => Outcome::Loss,
+    };
+
+    Round::cheat(rules, op, goal).map_err(|err| match err {
+        // Impossible outcomes point at the code that asked for them
+        Error::Solve(message) => {
+            let line = entry_line(instructions);
+            Error::parse(&line, &line[instructions[0].len() + 1..], message)
+        }
+        err => err,
+    })
 }
 
-pub fn part1(input: Data) -> (isize, Duration) {
-    let now = Instant::now();
-    let file = read_lines(get_filename("day2", input));
+// Total score with the second column as your shape. Without `strict`,
+// unknown codes fall back to a shape rather than failing.
+pub fn part1(rules: &Rules, strict: bool, guide: &[Entry]) -> SolveResult<isize> {
     let mut score = 0;
-    if let Ok(lines) = file {
-        for line in lines {
-            if let Ok(line) = line {
-                let r = parse_line(line);
-                score += r.score();
-            }
-        }
+    for (idx, instructions) in guide.iter().enumerate() {
+        score += parse_line(rules, strict, instructions).map_err(|err| err.at_line(idx + 1))?.score(rules);
     }
-    (score, now.elapsed())
+    Ok(score)
 }
 
-pub fn part2(input: Data) -> (isize, Duration) {
-    let now = Instant::now();
-    let file = read_lines(get_filename("day2", input));
+// Total score with the second column as the outcome you need
+pub fn part2(rules: &Rules, strict: bool, guide: &[Entry]) -> SolveResult<isize> {
     let mut score = 0;
-    if let Ok(lines) = file {
-        for line in lines {
-            if let Ok(line) = line {
-                score += parse_line_part2(line);
-            }
-        }
+    for (idx, instructions) in guide.iter().enumerate() {
+        score += parse_line_part2(rules, strict, instructions).map_err(|err| err.at_line(idx + 1))?.score(rules);
+    }
+    Ok(score)
+}
+
+examples! {
+    solution: Day2,
+    puzzle: {
+        input: "A Y\nB X\nC Z\n",
+        part1: "15",
+        part2: "12",
+    },
+    mirror: {
+        input: "A X\nB Y\nC Z\n",
+        part1: "15",
+        part2: "15",
+    },
+}
+
+impl Solution for Day2 {
+    const DAY: usize = 2;
+    const TITLE: &'static str = "Rock Paper Scissors";
+    const EXAMPLES: &'static [Example] = EXAMPLES;
+    type Parsed = Vec<Entry>;
+
+    fn parse(&self, input: &str) -> SolveResult<Self::Parsed> {
+        parse_lines(&split_lines(input), parse_entry)
+    }
+
+    fn part1(&self, guide: &Self::Parsed) -> SolveResult<Answer> {
+        Ok(part1(&Rules::classic(), false, guide)?.into())
+    }
+
+    fn part2(&self, guide: &Self::Parsed) -> SolveResult<Answer> {
+        Ok(part2(&Rules::classic(), false, guide)?.into())
     }
-    (score, now.elapsed())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    #[test]
-    fn part1() {
-        let (res, _dur) = super::part1(Data::Test);
-        let want = 15;
-        assert_eq!(want, res);
-    }
+
+    const ROCK: Hand = Hand(0);
+    const PAPER: Hand = Hand(1);
 
     #[test]
     fn parse_line_ay() {
-        let line = "A Y".to_string();
-        let res = super::parse_line(line);
+        let res = super::parse_line(&Rules::classic(), true, &parse_entry("A Y").unwrap()).unwrap();
         let want = Round{
-            opponent: Hand::Rock,
-            you: Hand::Paper};
+            opponent: ROCK,
+            you: PAPER};
         assert_eq!(want, res);
     }
 
     #[test]
     fn parse_line_bx() {
-        let line = "B X".to_string();
-        let res = super::parse_line(line);
+        let res = super::parse_line(&Rules::classic(), true, &parse_entry("B X").unwrap()).unwrap();
         let want = Round{
-            you: Hand::Rock,
-            opponent: Hand::Paper
+            you: ROCK,
+            opponent: PAPER
         };
         assert_eq!(want, res);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn rpsls() {
+        // Spock vaporizes rock (5 + 6), rock crushes scissors (1 + 6)
+        let rules = Rules::load("rpsls").unwrap();
+        let guide = parse_lines(&split_lines("A Z\nC V\n"), parse_entry).unwrap();
+        assert_eq!(11 + 7, super::part1(&rules, true, &guide).unwrap());
+        // Spock is the best win against rock, and lizard the best loss to
+        // scissors, as V isn't an outcome
+        assert_eq!(11 + 4, super::part2(&rules, false, &guide).unwrap());
+    }
+
+    #[test]
+    fn strict() {
+        let rules = Rules::classic();
+        let guide = parse_lines(&split_lines("A Y\nB Q\nC Z\n"), parse_entry).unwrap();
+        // Q used to count as scissors, and as a loss
+        assert_eq!(8 + 9 + 6, super::part1(&rules, false, &guide).unwrap());
+        assert_eq!(4 + 1 + 7, super::part2(&rules, false, &guide).unwrap());
+        for res in [super::part1(&rules, true, &guide), super::part2(&rules, true, &guide)] {
+            match res {
+                Err(Error::Parse(err)) => {
+                    assert_eq!((Some(2), 3), (err.line, err.column));
+                    assert!(err.message.contains("unknown code 'Q'"), "{}", err.message);
+                }
+                other => panic!("expected a parse error, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn overlapping_codes() {
+        let rules = Rules::parse("\
+shape rock 1
+shape paper 2
+shape scissors 3
+rock beats scissors
+paper beats rock
+scissors beats paper
+opponent rock R r
+opponent paper P
+opponent scissors S
+you rock P
+you paper S
+you scissors R
+").unwrap();
+        // Each column reads its own codes first, so P is rock for you and
+        // R is scissors; r is only the opponent's, so it's still rock
+        let guide = parse_lines(&split_lines("R P\nP R\nS r\n"), parse_entry).unwrap();
+        assert_eq!((1 + 3) + (3 + 6) + (1 + 6), super::part1(&rules, false, &guide).unwrap());
+        assert!(super::part1(&rules, true, &guide).is_err());
+    }
+
+    #[test]
+    fn parse_line_columns() {
+        for line in ["A", "A Y Z", "", "A  Y"] {
+            assert!(parse_entry(line).is_err(), "'{}' should not parse", line);
+        }
+    }
+}
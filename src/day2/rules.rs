@@ -0,0 +1,438 @@
+use std::fs;
+use std::path::Path;
+
+use crate::error::{parse_number, Error};
+
+// The rules of a game like Rock Paper Scissors: any number of shapes, which
+// shape beats which, the score for each shape and outcome, and the codes a
+// strategy guide uses for them. Written one statement per line, with `#`
+// comments:
+//
+//     shape rock 1
+//     shape paper 2
+//     shape scissors 3
+//     outcome loss 0
+//     outcome draw 3
+//     outcome win 6
+//     rock beats scissors
+//     paper beats rock
+//     scissors beats paper
+//     opponent rock A R
+//     you rock X
+//     need win Z
+//
+// Every pair of different shapes needs a winner, and the same shape draws.
+// Outcome scores default to 0, 3 and 6. A guide column with no codes given
+// uses letters: A, B, C, ... for the opponent and ..., X, Y, Z for you, in
+// the order the shapes are declared, and X, Y, Z for a loss, draw or win.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rules {
+    pub shapes: Vec<Shape>,
+    // beats[a][b] is true when shape a beats shape b
+    beats: Vec<Vec<bool>>,
+    // Loss, draw, win
+    outcomes: [isize; 3],
+    // Codes for the opponent's column, your column and, in part 2, the
+    // outcome you need
+    opponent: Vec<(String, usize)>,
+    you: Vec<(String, usize)>,
+    need: Vec<(String, Outcome)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Shape {
+    pub name: String,
+    pub score: isize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Loss,
+    Draw,
+    Win,
+}
+
+// Default letters run from A for the opponent and back from Z for you, so
+// the two columns can't overlap
+const MAX_LETTERED: usize = 13;
+
+const KEYWORDS: [&str; 7] = ["shape", "outcome", "beats", "opponent", "you", "need", "nothing"];
+
+const RPS: &str = "\
+# Rock Paper Scissors
+shape rock 1
+shape paper 2
+shape scissors 3
+rock beats scissors
+paper beats rock
+scissors beats paper
+";
+
+const RPSLS: &str = "\
+# Rock Paper Scissors Lizard Spock
+shape rock 1
+shape paper 2
+shape scissors 3
+shape lizard 4
+shape spock 5
+rock beats scissors lizard
+paper beats rock spock
+scissors beats paper lizard
+lizard beats paper spock
+spock beats rock scissors
+";
+
+pub const OUTCOMES: [Outcome; 3] = [Outcome::Loss, Outcome::Draw, Outcome::Win];
+
+pub const BUILTIN: [(&str, &str); 2] = [("rps", RPS), ("rpsls", RPSLS)];
+
+impl Outcome {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Outcome::Loss => "loss",
+            Outcome::Draw => "draw",
+            Outcome::Win => "win",
+        }
+    }
+
+    fn parse(name: &str) -> Option<Outcome> {
+        match name {
+            "loss" => Some(Outcome::Loss),
+            "draw" => Some(Outcome::Draw),
+            "win" => Some(Outcome::Win),
+            _ => None,
+        }
+    }
+}
+
+impl Rules {
+    // The puzzle's own rules
+    pub fn classic() -> Rules {
+        Rules::parse(RPS).expect("built in rules are valid")
+    }
+
+    // A built in game by name, or a rules file
+    pub fn load(spec: &str) -> Result<Rules, Error> {
+        if let Some((_, text)) = BUILTIN.iter().find(|(name, _)| *name == spec) {
+            return Rules::parse(text);
+        }
+        let text = fs::read_to_string(Path::new(spec)).map_err(|source| Error::IO { path: spec.to_string(), source })?;
+        Rules::parse(&text).map_err(|err| err.in_file(spec))
+    }
+
+    pub fn parse(text: &str) -> Result<Rules, Error> {
+        let mut rules = Rules {
+            shapes: Vec::new(),
+            beats: Vec::new(),
+            outcomes: [0, 3, 6],
+            opponent: Vec::new(),
+            you: Vec::new(),
+            need: Vec::new(),
+        };
+        // Where each shape was declared, for errors about the whole game
+        let mut declared: Vec<(usize, &str, &str)> = Vec::new();
+        for (idx, line) in text.lines().enumerate() {
+            let statement = line.split('#').next().unwrap_or("");
+            let words: Vec<&str> = statement.split_whitespace().collect();
+            let res = match words[..] {
+                [] => Ok(()),
+                ["shape", name, score] => rules.add_shape(line, name, score).map(|_| declared.push((idx + 1, line, name))),
+                ["shape", ..] => Err(Error::parse(line, statement.trim(), "expected 'shape NAME SCORE'")),
+                ["outcome", name, score] => match Outcome::parse(name) {
+                    Some(outcome) => parse_number(line, score).map(|score| rules.outcomes[outcome as usize] = score),
+                    None => Err(Error::parse(line, name, format!("unknown outcome '{}', expected loss, draw or win", name))),
+                },
+                ["outcome", ..] => Err(Error::parse(line, statement.trim(), "expected 'outcome loss|draw|win SCORE'")),
+                ["opponent" | "you", shape, ref codes @ ..] if !codes.is_empty() => rules.add_codes(line, words[0], shape, codes),
+                ["need", name, ref codes @ ..] if !codes.is_empty() => match Outcome::parse(name) {
+                    Some(outcome) => rules.add_need(line, outcome, codes),
+                    None => Err(Error::parse(line, name, format!("unknown outcome '{}', expected loss, draw or win", name))),
+                },
+                [winner, "beats", ref losers @ ..] if !losers.is_empty() => rules.add_beats(line, winner, losers),
+                _ => Err(Error::parse(
+                    line,
+                    statement.trim(),
+                    "expected 'shape NAME SCORE', 'outcome NAME SCORE', 'A beats B ...', 'opponent|you SHAPE CODE ...' or 'need OUTCOME CODE ...'",
+                )),
+            };
+            res.map_err(|err| err.at_line(idx + 1))?;
+        }
+
+        if declared.len() < 2 {
+            let line = text.lines().count().max(1);
+            return Err(Error::parse("", "", "a game needs at least two shapes").at_line(line));
+        }
+        for (a, &(line_no, line, name)) in declared.iter().enumerate() {
+            for (b, other) in rules.shapes.iter().enumerate().skip(a + 1) {
+                if !rules.beats[a][b] && !rules.beats[b][a] {
+                    let message = format!("neither {} nor {} beats the other", name, other.name);
+                    return Err(Error::parse(line, name, message).at_line(line_no));
+                }
+            }
+        }
+
+        let count = rules.shapes.len();
+        if rules.opponent.is_empty() || rules.you.is_empty() {
+            if let Some(&(line_no, line, name)) = declared.get(MAX_LETTERED) {
+                let message = format!("games with more than {} shapes need 'opponent' and 'you' codes", MAX_LETTERED);
+                return Err(Error::parse(line, name, message).at_line(line_no));
+            }
+        }
+        if rules.opponent.is_empty() {
+            rules.opponent = (0..count).map(|shape| (((b'A' + shape as u8) as char).to_string(), shape)).collect();
+        }
+        if rules.you.is_empty() {
+            rules.you = (0..count).map(|shape| (((b'Z' + 1 - (count - shape) as u8) as char).to_string(), shape)).collect();
+        }
+        if rules.need.is_empty() {
+            rules.need = OUTCOMES.iter().zip(["X", "Y", "Z"]).map(|(&outcome, code)| (code.to_string(), outcome)).collect();
+        }
+        for &(line_no, line, name) in &declared {
+            let shape = rules.known(line, name)?;
+            for (column, codes) in [("opponent", &rules.opponent), ("you", &rules.you)] {
+                if !codes.iter().any(|&(_, other)| other == shape) {
+                    let message = format!("{} has no '{}' code, though other shapes do", name, column);
+                    return Err(Error::parse(line, name, message).at_line(line_no));
+                }
+            }
+        }
+        if let Some(outcome) = OUTCOMES.iter().find(|&&outcome| !rules.need.iter().any(|&(_, other)| other == outcome)) {
+            let line = text.lines().count().max(1);
+            let message = format!("{} has no 'need' code, though other outcomes do", outcome.name());
+            return Err(Error::parse("", "", message).at_line(line));
+        }
+        Ok(rules)
+    }
+
+    fn add_shape(&mut self, line: &str, name: &str, score: &str) -> Result<(), Error> {
+        if self.shape(name).is_some() {
+            return Err(Error::parse(line, name, format!("shape '{}' is declared twice", name)));
+        }
+        if Outcome::parse(name).is_some() || KEYWORDS.contains(&name) {
+            return Err(Error::parse(line, name, format!("'{}' can't be used as a shape name", name)));
+        }
+        let score = parse_number(line, score)?;
+        self.shapes.push(Shape { name: name.to_string(), score });
+        for row in self.beats.iter_mut() {
+            row.push(false);
+        }
+        self.beats.push(vec![false; self.shapes.len()]);
+        Ok(())
+    }
+
+    fn add_beats(&mut self, line: &str, winner: &str, losers: &[&str]) -> Result<(), Error> {
+        let a = self.known(line, winner)?;
+        for &loser in losers {
+            let b = self.known(line, loser)?;
+            if a == b {
+                return Err(Error::parse(line, loser, format!("{} can't beat itself", loser)));
+            }
+            if self.beats[b][a] {
+                return Err(Error::parse(line, loser, format!("{} already beats {}", loser, winner)));
+            }
+            self.beats[a][b] = true;
+        }
+        Ok(())
+    }
+
+    fn add_codes(&mut self, line: &str, column: &str, shape: &str, codes: &[&str]) -> Result<(), Error> {
+        let shape = self.known(line, shape)?;
+        let existing = if column == "opponent" { &mut self.opponent } else { &mut self.you };
+        for &code in codes {
+            if existing.iter().any(|(other, _)| other == code) {
+                return Err(Error::parse(line, code, format!("'{}' is already a '{}' code", code, column)));
+            }
+            existing.push((code.to_string(), shape));
+        }
+        Ok(())
+    }
+
+    fn add_need(&mut self, line: &str, outcome: Outcome, codes: &[&str]) -> Result<(), Error> {
+        for &code in codes {
+            if self.need.iter().any(|(other, _)| other == code) {
+                return Err(Error::parse(line, code, format!("'{}' is already a 'need' code", code)));
+            }
+            self.need.push((code.to_string(), outcome));
+        }
+        Ok(())
+    }
+
+    fn known(&self, line: &str, name: &str) -> Result<usize, Error> {
+        self.shape(name).ok_or_else(|| {
+            Error::parse(line, name, format!("unknown shape '{}' (declare it with 'shape {} SCORE' first)", name, name))
+        })
+    }
+
+    pub fn shape(&self, name: &str) -> Option<usize> {
+        self.shapes.iter().position(|shape| shape.name == name)
+    }
+
+    // How a round goes for `you`
+    pub fn outcome(&self, you: usize, opponent: usize) -> Outcome {
+        if self.beats[you][opponent] {
+            Outcome::Win
+        } else if self.beats[opponent][you] {
+            Outcome::Loss
+        } else {
+            Outcome::Draw
+        }
+    }
+
+    pub fn outcome_score(&self, outcome: Outcome) -> isize {
+        self.outcomes[outcome as usize]
+    }
+
+    pub fn score(&self, you: usize, opponent: usize) -> isize {
+        self.shapes[you].score + self.outcome_score(self.outcome(you, opponent))
+    }
+
+    // The shape that gets `outcome` against `opponent`; when several do, the
+    // one scoring most, then the first declared
+    pub fn respond(&self, opponent: usize, outcome: Outcome) -> Option<usize> {
+        (0..self.shapes.len())
+            .filter(|&you| self.outcome(you, opponent) == outcome)
+            .min_by_key(|&you| (-self.shapes[you].score, you))
+    }
+
+    // The shape a code in the opponent's column stands for
+    pub fn opponent_shape(&self, code: &str) -> Option<usize> {
+        self.opponent.iter().find(|(other, _)| other == code).map(|&(_, shape)| shape)
+    }
+
+    // The shape a code in your column stands for
+    pub fn your_shape(&self, code: &str) -> Option<usize> {
+        self.you.iter().find(|(other, _)| other == code).map(|&(_, shape)| shape)
+    }
+
+    // The outcome a code in your column asks for, in part 2
+    pub fn needed(&self, code: &str) -> Option<Outcome> {
+        self.need.iter().find(|(other, _)| other == code).map(|&(_, outcome)| outcome)
+    }
+
+    pub fn opponent_codes(&self) -> Vec<&str> {
+        self.opponent.iter().map(|(code, _)| code.as_str()).collect()
+    }
+
+    pub fn your_codes(&self) -> Vec<&str> {
+        self.you.iter().map(|(code, _)| code.as_str()).collect()
+    }
+
+    pub fn need_codes(&self) -> Vec<&str> {
+        self.need.iter().map(|(code, _)| code.as_str()).collect()
+    }
+
+    // The shapes with their codes and scores, and who beats whom
+    pub fn describe(&self) -> String {
+        let codes = |codes: &[(String, usize)], shape: usize| -> String {
+            codes.iter().filter(|&&(_, other)| other == shape).map(|(code, _)| code.as_str()).collect::<Vec<_>>().join(" ")
+        };
+        let mut res = String::from("  shape       score  opponent  you    beats\n");
+        for (idx, shape) in self.shapes.iter().enumerate() {
+            let beaten: Vec<&str> = (0..self.shapes.len())
+                .filter(|&other| self.beats[idx][other])
+                .map(|other| self.shapes[other].name.as_str())
+                .collect();
+            let beaten = if beaten.is_empty() { String::from("nothing") } else { beaten.join(", ") };
+            res.push_str(&format!(
+                "  {:<10} {:>6}  {:<8}  {:<5}  {}\n",
+                shape.name, shape.score, codes(&self.opponent, idx), codes(&self.you, idx), beaten
+            ));
+        }
+        let outcomes: Vec<String> = OUTCOMES
+            .iter()
+            .map(|&outcome| {
+                let need: Vec<&str> = self.need.iter().filter(|&&(_, other)| other == outcome).map(|(code, _)| code.as_str()).collect();
+                format!("{} {} (need {})", outcome.name(), self.outcome_score(outcome), need.join(" "))
+            })
+            .collect();
+        res.push_str(&format!("Outcomes: {}\n", outcomes.join(", ")));
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin() {
+        let rps = Rules::classic();
+        assert_eq!(3, rps.shapes.len());
+        assert_eq!(Outcome::Win, rps.outcome(1, 0));
+        assert_eq!(Outcome::Loss, rps.outcome(0, 1));
+        assert_eq!(Outcome::Draw, rps.outcome(2, 2));
+        assert_eq!((Some(0), Some(0)), (rps.opponent_shape("A"), rps.your_shape("X")));
+        assert_eq!(Some(Outcome::Win), rps.needed("Z"));
+        assert_eq!(None, rps.opponent_shape("X"));
+
+        let rpsls = Rules::load("rpsls").unwrap();
+        let (spock, lizard) = (rpsls.shape("spock").unwrap(), rpsls.shape("lizard").unwrap());
+        assert_eq!(Outcome::Loss, rpsls.outcome(spock, lizard));
+        assert_eq!(4 + 6, rpsls.score(lizard, spock));
+        assert_eq!(Some(0), rpsls.your_shape("V"));
+        // Rock and scissors both beat lizard; scissors scores more
+        assert_eq!(rpsls.shape("scissors"), rpsls.respond(lizard, Outcome::Win));
+    }
+
+    #[test]
+    fn house_rules() {
+        let rules = Rules::parse("shape odd 5\nshape even 2 # cheap\noutcome win 10\nodd beats even\n").unwrap();
+        assert_eq!(5 + 10, rules.score(0, 1));
+        assert_eq!(2 + 3, rules.score(1, 1));
+        assert_eq!(None, rules.respond(0, Outcome::Win));
+    }
+
+    #[test]
+    fn codes() {
+        let text = "\
+shape rock 1
+shape paper 2
+shape scissors 3
+rock beats scissors
+paper beats rock
+scissors beats paper
+opponent rock R r
+opponent paper P
+opponent scissors S
+you rock 🪨
+you paper 📄
+you scissors ✂️
+need loss L
+need draw D
+need win W
+";
+        let rules = Rules::parse(text).unwrap();
+        assert_eq!(Some(0), rules.opponent_shape("r"));
+        assert_eq!(Some(2), rules.your_shape("✂️"));
+        assert_eq!(Some(Outcome::Draw), rules.needed("D"));
+        assert_eq!(None, rules.opponent_shape("A"));
+        assert_eq!(vec!["R", "r", "P", "S"], rules.opponent_codes());
+        assert!(rules.describe().contains("  rock            1  R r       🪨      scissors\n"), "{}", rules.describe());
+    }
+
+    #[test]
+    fn rules_errors() {
+        let table = [
+            ("shape rock\n", (1, 1)),
+            ("shape rock one\n", (1, 12)),
+            ("shape rock 1\nshape rock 2\n", (2, 7)),
+            ("shape rock 1\noutcome tie 3\n", (2, 9)),
+            ("shape rock 1\nshape paper 2\nrock beats stone\n", (3, 12)),
+            ("shape rock 1\nshape paper 2\nrock beats rock\n", (3, 12)),
+            ("shape rock 1\nshape paper 2\npaper beats rock\nrock beats paper\n", (4, 12)),
+            ("shape rock 1\nshape paper 2\nshape well 3\nrock beats paper\n", (1, 7)),
+            ("shape rock 1\n", (1, 1)),
+            ("rock paper scissors\n", (1, 1)),
+            ("shape rock 1\nshape paper 2\npaper beats rock\nopponent rock A\n", (2, 7)),
+            ("shape rock 1\nshape paper 2\npaper beats rock\nyou rock A\nyou paper A\n", (5, 11)),
+            ("shape rock 1\nshape paper 2\npaper beats rock\nneed win W\n", (4, 1)),
+            ("shape rock 1\nshape you 2\n", (2, 7)),
+        ];
+        for (text, want) in table {
+            match Rules::parse(text) {
+                Err(Error::Parse(err)) => assert_eq!(want, (err.line.unwrap_or(0), err.column), "{:?}", text),
+                other => panic!("{:?} should not parse, got {:?}", text, other),
+            }
+        }
+    }
+}
@@ -0,0 +1,356 @@
+use std::fmt::Write as _;
+
+use crate::error::Error;
+use crate::solution::SolveResult;
+
+use super::rules::{Outcome, Rules};
+use super::{unknown, Entry, Hand, Round};
+
+// SplitMix64: tiny, fast and good enough for games. The same seed always
+// gives the same tournament.
+#[derive(Debug, Clone)]
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> SplitMix64 {
+        SplitMix64 { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // Uniform in 0..n, for n > 0
+    pub fn below(&mut self, n: usize) -> usize {
+        ((self.next_u64() as u128 * n as u128) >> 64) as usize
+    }
+}
+
+// Strategies a bot can play
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Strategy {
+    // Your column of the guide, read as shapes, over and over
+    Guide,
+    // The opponent's column of the guide
+    Elf,
+    Random,
+    // Beats whatever the opponent has played most
+    Frequency,
+    // Beats whatever the opponent most often played after their last shape
+    Markov,
+}
+
+pub const STRATEGIES: [(&str, Strategy); 5] = [
+    ("guide", Strategy::Guide),
+    ("elf", Strategy::Elf),
+    ("random", Strategy::Random),
+    ("frequency", Strategy::Frequency),
+    ("markov", Strategy::Markov),
+];
+
+impl Strategy {
+    pub fn parse(name: &str) -> Option<Strategy> {
+        STRATEGIES.iter().find(|(other, _)| *other == name).map(|&(_, strategy)| strategy)
+    }
+
+    pub fn name(&self) -> &'static str {
+        STRATEGIES.iter().find(|(_, other)| other == self).map_or("?", |&(name, _)| name)
+    }
+
+    // Whether the bot needs a strategy guide to play
+    pub fn uses_guide(&self) -> bool {
+        matches!(self, Strategy::Guide | Strategy::Elf)
+    }
+}
+
+// One bot's state during a match
+struct Bot<'a> {
+    strategy: Strategy,
+    moves: &'a [usize],
+    // How often the opponent played each shape, and each shape after each other
+    seen: Vec<usize>,
+    after: Vec<Vec<usize>>,
+    last: Option<usize>,
+}
+
+impl<'a> Bot<'a> {
+    fn new(strategy: Strategy, guide: &'a Guide, shapes: usize) -> Bot<'a> {
+        let moves = match strategy {
+            Strategy::Guide => &guide.you[..],
+            Strategy::Elf => &guide.opponent[..],
+            _ => &[],
+        };
+        Bot { strategy, moves, seen: vec![0; shapes], after: vec![vec![0; shapes]; shapes], last: None }
+    }
+
+    fn play(&self, rules: &Rules, round: usize, rng: &mut SplitMix64) -> usize {
+        let shapes = rules.shapes.len();
+        let predicted = match self.strategy {
+            Strategy::Guide | Strategy::Elf => return self.moves[round % self.moves.len()],
+            Strategy::Random => return rng.below(shapes),
+            Strategy::Frequency => most(&self.seen),
+            Strategy::Markov => self.last.and_then(|last| most(&self.after[last])),
+        };
+        // Nothing to go on yet, or nothing beats the prediction
+        match predicted.and_then(|shape| rules.respond(shape, Outcome::Win)) {
+            Some(shape) => shape,
+            None => rng.below(shapes),
+        }
+    }
+
+    fn observe(&mut self, theirs: usize) {
+        self.seen[theirs] += 1;
+        if let Some(last) = self.last {
+            self.after[last][theirs] += 1;
+        }
+        self.last = Some(theirs);
+    }
+}
+
+// The most common shape, the first of any tie; None before any are seen
+fn most(counts: &[usize]) -> Option<usize> {
+    let best = (0..counts.len()).rev().max_by_key(|&shape| counts[shape])?;
+    if counts[best] == 0 { None } else { Some(best) }
+}
+
+// A strategy guide's columns, read as shapes
+#[derive(Debug, Default, PartialEq)]
+pub struct Guide {
+    pub opponent: Vec<usize>,
+    pub you: Vec<usize>,
+}
+
+impl Guide {
+    // Both columns must be shape codes
+    pub fn new(rules: &Rules, guide: &[Entry]) -> SolveResult<Guide> {
+        let mut res = Guide::default();
+        for (idx, instructions) in guide.iter().enumerate() {
+            let shapes = (rules.opponent_shape(&instructions[0]), rules.your_shape(&instructions[1]));
+            match shapes {
+                (Some(opponent), Some(you)) => {
+                    res.opponent.push(opponent);
+                    res.you.push(you);
+                }
+                (None, _) => return Err(unknown(instructions, 0, &rules.opponent_codes()).at_line(idx + 1)),
+                (_, None) => return Err(unknown(instructions, 1, &rules.your_codes()).at_line(idx + 1)),
+            }
+        }
+        if res.you.is_empty() {
+            return Err(Error::solve("the strategy guide is empty"));
+        }
+        Ok(res)
+    }
+}
+
+// One line of the league table
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Standing {
+    pub name: String,
+    pub played: usize,
+    pub won: usize,
+    pub drawn: usize,
+    pub lost: usize,
+    // Match points: 3 for a win, 1 for a draw
+    pub points: usize,
+    // Round scores, summed over every match
+    pub scored: isize,
+    pub conceded: isize,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct League {
+    pub rounds: usize,
+    pub seed: u64,
+    // Best first: points, then score difference, then entry order
+    pub table: Vec<Standing>,
+}
+
+// Every bot plays every other once, over `rounds` rounds. A match goes to
+// whoever scores more across its rounds.
+pub fn tournament(rules: &Rules, guide: &Guide, bots: &[Strategy], rounds: usize, seed: u64) -> SolveResult<League> {
+    if let Some(bot) = bots.iter().find(|bot| bot.uses_guide()) {
+        if guide.you.is_empty() {
+            return Err(Error::solve(format!("the {} bot needs a strategy guide", bot.name())));
+        }
+    }
+    let mut table: Vec<Standing> = bots
+        .iter()
+        .map(|strategy| Standing { name: strategy.name().to_string(), ..Standing::default() })
+        .collect();
+    // Number repeated entries, so "random" and "random" can be told apart
+    for idx in 0..bots.len() {
+        if bots.iter().filter(|&&other| other == bots[idx]).count() > 1 {
+            let nth = bots[..=idx].iter().filter(|&&other| other == bots[idx]).count();
+            table[idx].name = format!("{} {}", bots[idx].name(), nth);
+        }
+    }
+
+    let mut matches = SplitMix64::new(seed);
+    for a in 0..bots.len() {
+        for b in a + 1..bots.len() {
+            // Each match gets its own stream, so adding a bot doesn't change
+            // the matches already played
+            let mut rng = SplitMix64::new(matches.next_u64());
+            let (score_a, score_b) = play_match(rules, guide, bots[a], bots[b], rounds, &mut rng);
+            table[a].record(score_a, score_b);
+            table[b].record(score_b, score_a);
+        }
+    }
+
+    let mut order: Vec<usize> = (0..table.len()).collect();
+    order.sort_by_key(|&idx| (std::cmp::Reverse((table[idx].points, table[idx].scored - table[idx].conceded)), idx));
+    Ok(League {
+        rounds,
+        seed,
+        table: order.into_iter().map(|idx| table[idx].clone()).collect(),
+    })
+}
+
+fn play_match(rules: &Rules, guide: &Guide, a: Strategy, b: Strategy, rounds: usize, rng: &mut SplitMix64) -> (isize, isize) {
+    let shapes = rules.shapes.len();
+    let (mut bot_a, mut bot_b) = (Bot::new(a, guide, shapes), Bot::new(b, guide, shapes));
+    let (mut score_a, mut score_b) = (0, 0);
+    for round in 0..rounds {
+        let (hand_a, hand_b) = (bot_a.play(rules, round, rng), bot_b.play(rules, round, rng));
+        score_a += Round { you: Hand(hand_a), opponent: Hand(hand_b) }.score(rules);
+        score_b += Round { you: Hand(hand_b), opponent: Hand(hand_a) }.score(rules);
+        bot_a.observe(hand_b);
+        bot_b.observe(hand_a);
+    }
+    (score_a, score_b)
+}
+
+impl Standing {
+    fn record(&mut self, scored: isize, conceded: isize) {
+        self.played += 1;
+        self.scored += scored;
+        self.conceded += conceded;
+        if scored > conceded {
+            self.won += 1;
+            self.points += 3;
+        } else if scored == conceded {
+            self.drawn += 1;
+            self.points += 1;
+        } else {
+            self.lost += 1;
+        }
+    }
+}
+
+impl League {
+    pub fn text(&self) -> String {
+        let width = self.table.iter().map(|standing| standing.name.chars().count()).max().unwrap_or(0).max("Bot".len());
+        let mut res = String::new();
+        writeln!(res, "{} rounds per match, seed {}", self.rounds, self.seed).unwrap();
+        writeln!(
+            res,
+            "Pos  {:<width$}   P   W   D   L  Pts  {:>9}  {:>9}  {:>8}",
+            "Bot", "Scored", "Conceded", "Diff", width = width
+        ).unwrap();
+        for (pos, standing) in self.table.iter().enumerate() {
+            writeln!(
+                res,
+                "{:>3}  {:<width$}  {:>2}  {:>2}  {:>2}  {:>2}  {:>3}  {:>9}  {:>9}  {:>+8}",
+                pos + 1, standing.name, standing.played, standing.won, standing.drawn, standing.lost, standing.points,
+                standing.scored, standing.conceded, standing.scored - standing.conceded,
+                width = width
+            ).unwrap();
+        }
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day2::{parse_entry, EXAMPLES};
+    use crate::util::{parse_lines, split_lines};
+
+    fn example_guide(rules: &Rules) -> Guide {
+        Guide::new(rules, &parse_lines(&split_lines(EXAMPLES[0].input), parse_entry).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn splitmix() {
+        // Reference outputs for seed 0
+        let mut rng = SplitMix64::new(0);
+        assert_eq!(0xE220_A839_7B1D_CDAF, rng.next_u64());
+        assert_eq!(0x6E78_9E6A_A1B9_65F4, rng.next_u64());
+        assert!((0..1000).all(|_| rng.below(3) < 3));
+    }
+
+    #[test]
+    fn guide_against_elf() {
+        // Following the guide against the elf it was written for scores
+        // part 1, and the example's elf happens to score the same
+        let rules = Rules::classic();
+        let guide = example_guide(&rules);
+        let league = tournament(&rules, &guide, &[Strategy::Guide, Strategy::Elf], 3, 1).unwrap();
+        let guide_bot = &league.table[0];
+        assert_eq!(("guide", 15, 15), (guide_bot.name.as_str(), guide_bot.scored, guide_bot.conceded));
+        assert_eq!((0, 1, 0, 1), (guide_bot.won, guide_bot.drawn, guide_bot.lost, guide_bot.points));
+
+        // Over two passes of the guide the totals double
+        let league = tournament(&rules, &guide, &[Strategy::Guide, Strategy::Elf], 6, 1).unwrap();
+        assert_eq!(30, league.table[0].scored);
+    }
+
+    #[test]
+    fn predictors() {
+        // The elf repeats rock, paper, scissors, which Markov learns and a
+        // frequency count can't
+        let rules = Rules::classic();
+        let guide = example_guide(&rules);
+        let league = tournament(&rules, &guide, &[Strategy::Elf, Strategy::Frequency, Strategy::Markov], 300, 7).unwrap();
+        let names: Vec<&str> = league.table.iter().map(|standing| standing.name.as_str()).collect();
+        assert_eq!("markov", names[0]);
+        let markov = &league.table[0];
+        assert_eq!((2, 0, 0, 6), (markov.won, markov.drawn, markov.lost, markov.points));
+    }
+
+    #[test]
+    fn wide_names() {
+        let table = vec![Standing { name: "élf".to_string(), ..Standing::default() }];
+        let text = League { rounds: 1, seed: 0, table }.text();
+        assert!(text.contains("\n  1  élf   0"), "{}", text);
+    }
+
+    #[test]
+    fn seeded() {
+        let rules = Rules::load("rpsls").unwrap();
+        let guide = Guide { opponent: vec![0, 4], you: vec![1, 2] };
+        let bots = [Strategy::Random, Strategy::Random, Strategy::Frequency, Strategy::Guide];
+        let league = tournament(&rules, &guide, &bots, 1000, 2022).unwrap();
+        assert_eq!(league, tournament(&rules, &guide, &bots, 1000, 2022).unwrap());
+        assert_ne!(league, tournament(&rules, &guide, &bots, 1000, 2023).unwrap());
+        assert!(league.table.iter().any(|standing| standing.name == "random 2"));
+        let played: usize = league.table.iter().map(|standing| standing.played).sum();
+        assert_eq!(12, played);
+
+        assert!(tournament(&rules, &Guide::default(), &bots, 10, 1).is_err());
+
+        // Pinned, so a change to the bots or the generator shows up here
+        let rules = Rules::classic();
+        let bots: Vec<Strategy> = STRATEGIES.iter().map(|&(_, strategy)| strategy).collect();
+        let league = tournament(&rules, &example_guide(&rules), &bots, 100, 2022).unwrap();
+        let table: Vec<(&str, usize, isize, isize)> = league.table
+            .iter()
+            .map(|standing| (standing.name.as_str(), standing.points, standing.scored, standing.conceded))
+            .collect();
+        let want = vec![
+            ("markov", 12, 2764, 1274),
+            ("guide", 6, 1889, 2130),
+            ("frequency", 6, 1708, 2324),
+            ("random", 3, 1972, 2038),
+            ("elf", 3, 1708, 2275),
+        ];
+        assert_eq!(want, table);
+        assert!(tournament(&rules, &Guide::default(), &[Strategy::Random, Strategy::Markov], 10, 1).is_ok());
+    }
+}
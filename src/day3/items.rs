@@ -0,0 +1,216 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::error::Error;
+use crate::solution::SolveResult;
+
+// The puzzle's items: a-z have priorities 1 to 26, and A-Z 27 to 52
+pub const LETTERS: &str = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+// Maps item symbols to priorities, 1 for the first
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alphabet {
+    items: Vec<char>,
+    // Priorities of ASCII items, 0 for none, so the usual case skips hashing
+    ascii: [usize; 128],
+    other: HashMap<char, usize>,
+}
+
+impl Alphabet {
+    // The puzzle's own alphabet
+    pub fn classic() -> Alphabet {
+        Alphabet::parse(LETTERS).expect("the built in alphabet is valid")
+    }
+
+    // "letters" for the puzzle's alphabet, or an ordering file
+    pub fn load(spec: &str) -> Result<Alphabet, Error> {
+        if spec == "letters" {
+            return Ok(Alphabet::classic());
+        }
+        let text = fs::read_to_string(Path::new(spec)).map_err(|source| Error::IO { path: spec.to_string(), source })?;
+        Alphabet::parse(&text).map_err(|err| err.in_file(spec))
+    }
+
+    // Every character other than whitespace is the next item, so priorities
+    // follow the order of the text. A line starting with # is a comment.
+    pub fn parse(text: &str) -> Result<Alphabet, Error> {
+        let mut res = Alphabet { items: Vec::new(), ascii: [0; 128], other: HashMap::new() };
+        for (idx, line) in text.lines().enumerate() {
+            if line.starts_with('#') {
+                continue;
+            }
+            for (at, c) in line.char_indices().filter(|(_, c)| !c.is_whitespace()) {
+                if let Some(priority) = res.priority(c) {
+                    let message = format!("'{}' already has priority {}", c.escape_debug(), priority);
+                    return Err(Error::parse(line, &line[at..at + c.len_utf8()], message).at_line(idx + 1));
+                }
+                res.items.push(c);
+                let priority = res.items.len();
+                match res.ascii.get_mut(c as usize) {
+                    Some(slot) => *slot = priority,
+                    None => {
+                        res.other.insert(c, priority);
+                    }
+                }
+            }
+        }
+        if res.items.is_empty() {
+            return Err(Error::solve("the alphabet has no items"));
+        }
+        Ok(res)
+    }
+
+    pub fn priority(&self, item: char) -> Option<usize> {
+        match self.ascii.get(item as usize) {
+            Some(0) => None,
+            Some(&priority) => Some(priority),
+            None => self.other.get(&item).copied(),
+        }
+    }
+
+    pub fn item(&self, priority: usize) -> char {
+        priority.checked_sub(1).and_then(|idx| self.items.get(idx)).copied().unwrap_or('?')
+    }
+
+    // Number of items, which is also the highest priority
+    pub fn size(&self) -> usize {
+        self.items.len()
+    }
+
+    // What an item should have been, for errors
+    fn expected(&self) -> String {
+        if self.items.iter().collect::<String>() == LETTERS {
+            String::from("a-z or A-Z")
+        } else if self.items.len() <= 10 {
+            let items: Vec<String> = self.items.iter().map(|item| item.to_string()).collect();
+            items.join(", ")
+        } else {
+            format!("one of the alphabet's {} items", self.items.len())
+        }
+    }
+}
+
+// A set of items, one bit per priority, so intersecting rucksacks is a few
+// ANDs however big they are. Sets from the same alphabet are the same size.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Items(Vec<u64>);
+
+impl Items {
+    pub fn empty(alphabet: &Alphabet) -> Items {
+        Items(vec![0; alphabet.size() / 64 + 1])
+    }
+
+    // `text` is a slice of `line`, which is only needed for error reports
+    pub fn parse(alphabet: &Alphabet, line: &str, text: &str) -> SolveResult<Items> {
+        let mut res = Items::empty(alphabet);
+        for (idx, c) in text.char_indices() {
+            match alphabet.priority(c) {
+                Some(priority) => res.0[priority / 64] |= 1 << (priority % 64),
+                None => {
+                    let bad = &text[idx..idx + c.len_utf8()];
+                    let message = format!("'{}' isn't an item (expected {})", c.escape_debug(), alphabet.expected());
+                    return Err(Error::parse(line, bad, message));
+                }
+            }
+        }
+        Ok(res)
+    }
+
+    pub fn intersect(&self, other: &Items) -> Items {
+        Items(self.0.iter().zip(&other.0).map(|(a, b)| a & b).collect())
+    }
+
+    pub fn union(&self, other: &Items) -> Items {
+        Items(self.0.iter().zip(&other.0).map(|(a, b)| a | b).collect())
+    }
+
+    pub fn count(&self) -> usize {
+        self.0.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    // Priorities in the set, lowest first
+    pub fn priorities(&self) -> impl Iterator<Item = usize> + '_ {
+        self.0.iter().enumerate().flat_map(|(idx, &word)| {
+            (0..64).filter(move |bit| word & (1 << bit) != 0).map(move |bit| idx * 64 + bit)
+        })
+    }
+
+    // The one item in the set; `what` says what the set is, for the error
+    pub fn only(&self, alphabet: &Alphabet, what: &str) -> Result<usize, String> {
+        match self.count() {
+            1 => Ok(self.priorities().next().unwrap_or(0)),
+            0 => Err(format!("no item is in {}", what)),
+            n => Err(format!("{} items are in {}: {}", n, what, self.names(alphabet))),
+        }
+    }
+
+    // "a, B, c"
+    pub fn names(&self, alphabet: &Alphabet) -> String {
+        let items: Vec<String> = self.priorities().map(|priority| alphabet.item(priority).to_string()).collect();
+        items.join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn priorities() {
+        let letters = Alphabet::classic();
+        let priority = |item| letters.priority(item);
+        assert_eq!((Some(1), Some(26), Some(27), Some(52)), (priority('a'), priority('z'), priority('A'), priority('Z')));
+        assert_eq!((None, None), (priority('é'), priority('1')));
+        assert!((1..=52).all(|p| priority(letters.item(p)) == Some(p)));
+    }
+
+    #[test]
+    fn sets() {
+        let letters = Alphabet::classic();
+        let parse = |text| Items::parse(&letters, text, text).unwrap();
+        let items = parse("vJrwpWtwJgWr");
+        assert_eq!(8, items.count());
+        let shared = items.intersect(&parse("rJx"));
+        assert_eq!("r, J", shared.names(&letters));
+        assert!(shared.only(&letters, "both").unwrap_err().starts_with("2 items are in both"));
+        assert_eq!(Ok(18), shared.intersect(&parse("r")).only(&letters, "all"));
+        assert!(Items::empty(&letters).only(&letters, "all").is_err());
+
+        match Items::parse(&letters, "ab-c", "ab-c") {
+            Err(Error::Parse(err)) => assert_eq!(3, err.column),
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn custom_alphabets() {
+        // Unicode items, across more than one word of bits
+        let symbols: String = ('α'..='ω').chain('一'..='乙').collect();
+        let alphabet = Alphabet::parse(&format!("# Greek, then CJK\n🍎 🍌\n{}\n", symbols)).unwrap();
+        assert_eq!(2 + symbols.chars().count(), alphabet.size());
+        assert_eq!((Some(1), Some(3)), (alphabet.priority('🍎'), alphabet.priority('α')));
+        let last = alphabet.item(alphabet.size());
+        let line = format!("🍌{}α{}", last, last);
+        let items = Items::parse(&alphabet, &line, &line).unwrap();
+        assert_eq!(vec![2, 3, alphabet.size()], items.priorities().collect::<Vec<_>>());
+        assert_eq!(format!("🍌, α, {}", last), items.names(&alphabet));
+
+        match Items::parse(&alphabet, "🍎a", "🍎a") {
+            Err(Error::Parse(err)) => {
+                assert_eq!(2, err.column);
+                assert!(err.message.contains("one of the alphabet's"), "{}", err.message);
+            }
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+        match Alphabet::parse("xyz\n\nzw\n") {
+            Err(Error::Parse(err)) => {
+                assert_eq!((Some(3), 1), (err.line, err.column));
+                assert!(err.message.contains("already has priority 3"), "{}", err.message);
+            }
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+        assert!(Alphabet::parse("# nothing\n \n").is_err());
+        assert!(Alphabet::load("no/such/alphabet").is_err());
+    }
+}
@@ -1,157 +1,305 @@
-// use super::util::*;
-
-use super::util::read_lines;
-use super::util::get_filename;
-use std::time::{Instant, Duration};
-
-// Split the rucksack into its two compartments
-fn split_compartments(rucksack: &str) -> Vec<&str> {
-    let len = rucksack.len();
-    let (comp1, comp2) = rucksack.split_at(len/2);
-    return vec![comp1, comp2];
-}
-
-// Exactly one item in each rucksack appears in both compartments
-fn find_repeat(rucksack: Vec<&str>) -> char {
-    let mut comp1:Vec<char> = rucksack[0].chars().collect::<Vec<char>>();
-    let mut comp2:Vec<char> = rucksack[1].chars().collect::<Vec<char>>();
-    comp1.sort_by(|a, b| a.cmp(b));
-    comp2.sort_by(|a, b| a.cmp(b));
-    // println!("{}", comp1.clone().iter().collect::<String>());
-    // println!("{}", comp2.clone().iter().collect::<String>());
-
-    let mut i = 0;
-    let mut j = 0;
-    loop {
-        // println!("  Comparing {} and {}...", comp1[i], comp2[j]);
-        if i > comp1.len() || j > comp2.len() {
-            return ' ';
-        } else if comp1[i] == comp2[j] {
-            return comp1[i];
-        } else if comp2[j] > comp1[i] {
-            i += 1;
-        } else if comp2[j] < comp1[i] {
-            j += 1;
-        }
+use crate::error::Error;
+use crate::solution::{examples, Answer, Example, Solution, SolveResult};
+use crate::util::{parse_lines, split_lines};
+
+use items::{Alphabet, Items};
+
+pub mod items;
+pub mod shared;
+
+pub struct Day3;
+
+// A rucksack's line, and the items in each of its two compartments
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rucksack {
+    pub line: String,
+    pub left: Items,
+    pub right: Items,
+}
+
+// Byte offset of the middle item, as items can be any character
+fn middle(rucksack: &str) -> usize {
+    let items = rucksack.chars().count();
+    rucksack.char_indices().nth(items / 2).map_or(rucksack.len(), |(idx, _)| idx)
+}
+
+// Split the rucksack into its two compartments, with as many items each
+fn split_compartments(rucksack: &str) -> SolveResult<Vec<&str>> {
+    let items = rucksack.chars().count();
+    if !items.is_multiple_of(2) {
+        let message = format!("{} items can't be split into two equal compartments", items);
+        return Err(Error::parse(rucksack, &rucksack[rucksack.len()..], message));
     }
+    let (comp1, comp2) = rucksack.split_at(middle(rucksack));
+    Ok(vec![comp1, comp2])
 }
 
-fn get_priority(letter: char) -> usize {
-    let mut priority: usize = 0;
-    if letter.is_uppercase() {
-        priority += 26;
+impl Rucksack {
+    fn parse(alphabet: &Alphabet, line: &str) -> SolveResult<Rucksack> {
+        // A stray character is pointed at rather than blamed for an odd length
+        let compartments = split_compartments(line).map_err(|err| Items::parse(alphabet, line, line).err().unwrap_or(err))?;
+        let left = Items::parse(alphabet, line, compartments[0])?;
+        let right = Items::parse(alphabet, line, compartments[1])?;
+        Ok(Rucksack { line: line.to_string(), left, right })
     }
-    let priority_map = "abcdefghijklmnopqrstuvwxyz".to_string();
-    for (idx, value) in priority_map.chars().enumerate() {
-        if letter.to_ascii_lowercase() == value {
-            priority += idx + 1;
-        }
+
+    pub fn items(&self) -> Items {
+        self.left.union(&self.right)
+    }
+
+    // The two halves of the line
+    pub fn compartments(&self) -> (&str, &str) {
+        self.line.split_at(middle(&self.line))
     }
+}
+
+// One rucksack per line, with items from `alphabet`
+pub fn parse_rucksacks(alphabet: &Alphabet, input: &str) -> SolveResult<Vec<Rucksack>> {
+    parse_lines(&split_lines(input), |line| Rucksack::parse(alphabet, line))
+}
+
+// Exactly one item in each rucksack appears in both compartments; returns
+// its priority
+fn find_repeat(alphabet: &Alphabet, rucksack: &Rucksack) -> SolveResult<usize> {
+    rucksack
+        .left
+        .intersect(&rucksack.right)
+        .only(alphabet, "both compartments")
+        .map_err(|message| Error::parse(&rucksack.line, &rucksack.line, message))
+}
 
-    priority
+// The one item every elf in the group carries; returns its priority.
+// `first` is the group's first line, for the error.
+fn get_badge(alphabet: &Alphabet, group: &[Rucksack], first: usize) -> SolveResult<usize> {
+    let common = group.iter().fold(group[0].items(), |common, rucksack| common.intersect(&rucksack.items()));
+    common.only(alphabet, &format!("every rucksack on {}", lines(first, group.len()).to_lowercase())).map_err(|message| {
+        let last = &group[group.len() - 1].line;
+        Error::parse(last, last, message)
+    })
 }
 
-fn next_item(elf: Vec<char>, idx: usize) -> usize {
-    let mut new_idx = idx;
-    while new_idx < elf.len() {
-        if elf[new_idx] != elf[idx] {
-            return new_idx;
-        } 
-        new_idx +=1;
+// Sum of the priorities of the items in both compartments
+pub fn part1(alphabet: &Alphabet, rucksacks: &[Rucksack]) -> SolveResult<usize> {
+    let mut res = 0;
+    for (idx, rucksack) in rucksacks.iter().enumerate() {
+        res += find_repeat(alphabet, rucksack).map_err(|err| err.at_line(idx + 1))?;
     }
-    return elf.len()-1;
+    Ok(res)
 }
 
-fn get_badge(elf1: Vec<char>, elf2: Vec<char>, elf3: Vec<char>) -> char {
-    let (mut i, mut j, mut k) = (0, 0, 0);
+// What to do with the rucksacks left over when there aren't enough to make
+// a last full group
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Leftover {
+    // Fail, as the puzzle's own input never has any
+    Error,
+    // Leave them out
+    Ignore,
+    // Find the badge of the smaller group they make
+    Partial,
+}
 
-    loop {
-        // println!("  Comparing {}, {} and {}", elf1[i], elf2[j], elf3[k]);
-        if i >= elf1.len() || j >= elf2.len() || k >= elf3.len() {
-            break;
-        }
-        if elf1[i] == elf2[j] && elf2[j] == elf3[k] {
-            return elf1[i];
-        } else {
-            let current_items = vec![elf1[i], elf2[j], elf3[k]];
-            let min = current_items.iter().min();
-            if let Some(badge) = min{
-                if elf1[i] == *badge {
-                    i = next_item(elf1.clone(), i);
-                } else if elf2[j] == *badge {
-                    j = next_item(elf2.clone(), j);
-                } else if elf3[k] == *badge {
-                    k = next_item(elf3.clone(), k);
+// A group's badge, and the lines its rucksacks are on
+#[derive(Debug, Clone, PartialEq)]
+pub struct Badge {
+    pub first_line: usize,
+    pub rucksacks: usize,
+    pub priority: usize,
+}
+
+// Splits the rucksacks into groups of `size`, in order, each with the line
+// it starts on. The leftover policy decides what happens to a short last group.
+pub fn groups(rucksacks: &[Rucksack], size: usize, leftover: Leftover) -> SolveResult<Vec<(usize, &[Rucksack])>> {
+    if size == 0 {
+        return Err(Error::solve("groups need at least one rucksack"));
+    }
+    let mut res = Vec::with_capacity(rucksacks.len() / size + 1);
+    for (idx, group) in rucksacks.chunks(size).enumerate() {
+        if group.len() < size {
+            match leftover {
+                Leftover::Error => {
+                    let last = &group[group.len() - 1].line;
+                    let message = format!("incomplete group, expected {} rucksacks", size);
+                    return Err(Error::parse(last, &last[last.len()..], message).at_line(idx * size + group.len()));
                 }
+                Leftover::Ignore => break,
+                Leftover::Partial => (),
             }
         }
+        res.push((idx * size + 1, group));
     }
-    return ' '
+    Ok(res)
 }
 
-pub fn part1(input: super::Data) -> (usize, Duration) {
-    let now = Instant::now();
-    let file = read_lines(get_filename("day3", input));
-    let mut res = 0;
-    if let Ok(lines) = file {
-        for line in lines {
-            if let Ok(line) = line {
-                let repeat = find_repeat(split_compartments(line.as_str()));
-                res += get_priority(repeat);
-            }
-        }
+// "Line 7" or "Lines 4-6", for a group starting on `first_line`
+pub fn lines(first_line: usize, rucksacks: usize) -> String {
+    match rucksacks {
+        1 => format!("Line {}", first_line),
+        n => format!("Lines {}-{}", first_line, first_line + n - 1),
     }
-    (res, now.elapsed())
-}
-
-pub fn part2(input: super::Data) -> (usize, Duration) {
-    let now = Instant::now();
-    // Get file
-    let file = read_lines(get_filename("day3", input));
-    let mut pri = 0;
-    if let Ok(mut lines) = file {
-        loop {
-            let elf1 = lines.next();
-            let elf2 = lines.next();
-            let elf3 = lines.next();
-            if let None = elf1 {
-                break;
-            }
-            // Convert to char vectors
-            let mut elf1 = elf1.unwrap().unwrap().chars().collect::<Vec<char>>();
-            let mut elf2 = elf2.unwrap().unwrap().chars().collect::<Vec<char>>();
-            let mut elf3 = elf3.unwrap().unwrap().chars().collect::<Vec<char>>();
-
-            // Sort
-            elf1.sort_by(|a, b| a.cmp(b));
-            elf2.sort_by(|a, b| a.cmp(b));
-            elf3.sort_by(|a, b| a.cmp(b));
-            // println!(
-            //     "{}\n{}\n{}",
-            //     elf1.clone().iter().collect::<String>(),
-            //     elf2.clone().iter().collect::<String>(),
-            //     elf3.clone().iter().collect::<String>()
-            // );
-            let badge = get_badge(elf1, elf2, elf3);
-            pri += get_priority(badge);
-        }
+}
+
+// The one item every rucksack in each group carries
+pub fn badges(alphabet: &Alphabet, rucksacks: &[Rucksack], size: usize, leftover: Leftover) -> SolveResult<Vec<Badge>> {
+    let mut res = Vec::with_capacity(rucksacks.len() / size.max(1) + 1);
+    for (first_line, group) in groups(rucksacks, size, leftover)? {
+        let priority = get_badge(alphabet, group, first_line).map_err(|err| err.at_line(first_line + group.len() - 1))?;
+        res.push(Badge { first_line, rucksacks: group.len(), priority });
+    }
+    Ok(res)
+}
+
+// Sum of the priorities of each group of three's badge
+pub fn part2(alphabet: &Alphabet, rucksacks: &[Rucksack]) -> SolveResult<usize> {
+    Ok(badges(alphabet, rucksacks, 3, Leftover::Error)?.iter().map(|badge| badge.priority).sum())
+}
+
+examples! {
+    solution: Day3,
+    puzzle: {
+        input: "vJrwpWtwJgWrhcsFMMfFFhFp
+jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL
+PmmdzqPrVvPwwTWBwg
+wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn
+ttgJtRGJQctTZtZT
+CrZsJsPPZsGzwwsLwLmpwMDw
+",
+        part1: "157",
+        part2: "70",
+    },
+}
+
+impl Solution for Day3 {
+    const DAY: usize = 3;
+    const TITLE: &'static str = "Rucksack Reorganization";
+    const EXAMPLES: &'static [Example] = EXAMPLES;
+    type Parsed = Vec<Rucksack>;
+
+    fn parse(&self, input: &str) -> SolveResult<Self::Parsed> {
+        parse_rucksacks(&Alphabet::classic(), input)
+    }
+
+    fn part1(&self, rucksacks: &Self::Parsed) -> SolveResult<Answer> {
+        Ok(part1(&Alphabet::classic(), rucksacks)?.into())
+    }
+
+    fn part2(&self, rucksacks: &Self::Parsed) -> SolveResult<Answer> {
+        Ok(part2(&Alphabet::classic(), rucksacks)?.into())
     }
-    (pri, now.elapsed())
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+
+    fn rucksack(line: &str) -> Rucksack {
+        Rucksack::parse(&Alphabet::classic(), line).unwrap()
+    }
+
+    fn rucksacks(lines: &[&str]) -> Vec<Rucksack> {
+        lines.iter().map(|line| rucksack(line)).collect()
+    }
+
+    fn line_of(err: Error) -> Option<usize> {
+        match err {
+            Error::Parse(err) => err.line,
+            _ => panic!("expected a parse error, got {}", err),
+        }
+    }
+
     #[test]
     fn split_compartments() {
         let want = vec!["abcd", "efgh"];
-        let res = super::split_compartments("abcdefgh");
+        let res = super::split_compartments("abcdefgh").unwrap();
         assert_eq!(want, res);
     }
-    
+
     #[test]
     fn find_repeat() {
-        let want = 'c';
-        let res = super::find_repeat(vec!["abEcd", "BRcFG"]);
+        let letters = Alphabet::classic();
+        let want = letters.priority('c').unwrap();
+        let res = super::find_repeat(&letters, &rucksack("abEcdBRcFG")).unwrap();
         assert_eq!(want, res);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn find_repeat_none() {
+        let letters = Alphabet::classic();
+        let err = super::find_repeat(&letters, &rucksack("abcxyz")).unwrap_err();
+        assert!(err.to_string().contains("no item is in both compartments"), "{}", err);
+        let err = super::find_repeat(&letters, &rucksack("abcabd")).unwrap_err();
+        assert!(err.to_string().contains("2 items are in both compartments: a, b"), "{}", err);
+        assert_eq!(Some(2), line_of(part1(&letters, &rucksacks(&["aa", "ab"])).unwrap_err()));
+    }
+
+    #[test]
+    fn bad_rucksacks() {
+        let input = "vJrwpWtwJgWr\nabc\n";
+        match Day3.parse(input) {
+            Err(Error::Parse(err)) => {
+                assert_eq!((Some(2), 4), (err.line, err.column));
+                assert!(err.message.contains("3 items"), "{}", err.message);
+            }
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+        match Day3.parse("ab1c\n") {
+            Err(Error::Parse(err)) => assert_eq!((Some(1), 3), (err.line, err.column)),
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+        // Not a letter, even if it's a whole number of bytes per compartment
+        assert!(Day3.parse("aéba\n").is_err());
+    }
+
+    #[test]
+    fn unicode_items() {
+        let alphabet = Alphabet::parse("éa🎁ß").unwrap();
+        let input = "é🎁aé\n🎁ßßa\n🎁🎁\n";
+        let rucksacks = parse_rucksacks(&alphabet, input).unwrap();
+        // Halves are split by items, not bytes
+        assert_eq!(("é🎁", "aé"), rucksacks[0].compartments());
+        assert_eq!(1 + 4 + 3, part1(&alphabet, &rucksacks).unwrap());
+        assert_eq!(3, part2(&alphabet, &rucksacks).unwrap());
+
+        match parse_rucksacks(&alphabet, "éb\n") {
+            Err(Error::Parse(err)) => {
+                assert_eq!((Some(1), 2), (err.line, err.column));
+                assert!(err.message.contains("'b' isn't an item (expected é, a, 🎁, ß)"), "{}", err.message);
+            }
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn part2_badges() {
+        let letters = Alphabet::classic();
+        let err = part2(&letters, &rucksacks(&["ab", "bc", "bd", "ef"])).unwrap_err();
+        assert_eq!(Some(4), line_of(err));
+        let err = part2(&letters, &rucksacks(&["aa", "bb", "cc", "aa", "ab", "ac"])).unwrap_err();
+        assert!(err.to_string().contains("no item is in every rucksack on lines 1-3"), "{}", err);
+        assert_eq!(Some(3), line_of(err));
+        let err = part2(&letters, &rucksacks(&["abab", "abab", "abab"])).unwrap_err();
+        assert!(err.to_string().contains("2 items are in every rucksack"), "{}", err);
+    }
+
+    #[test]
+    fn group_sizes() {
+        let group = rucksacks(&["ab", "bc", "bd", "be", "xb", "xx", "xy"]);
+        let priorities = |res: SolveResult<Vec<Badge>>| res.unwrap().iter().map(|badge| badge.priority).collect::<Vec<_>>();
+        let letters = Alphabet::classic();
+        let b = letters.priority('b').unwrap();
+        assert_eq!(vec![b], priorities(badges(&letters, &group[..5], 5, Leftover::Error)));
+        assert_eq!(vec![b, b], priorities(badges(&letters, &group[..4], 2, Leftover::Error)));
+        assert_eq!(vec![b], priorities(badges(&letters, &group, 4, Leftover::Ignore)));
+
+        // The last two make a group of their own, with only x in common
+        let res = badges(&letters, &group[1..], 4, Leftover::Partial).unwrap();
+        assert_eq!(Badge { first_line: 5, rucksacks: 2, priority: letters.priority('x').unwrap() }, res[1]);
+
+        assert_eq!(("Line 7", "Lines 4-6"), (lines(7, 1).as_str(), lines(4, 3).as_str()));
+
+        let err = badges(&letters, &group, 2, Leftover::Error).unwrap_err();
+        assert!(err.to_string().contains("expected 2 rucksacks"), "{}", err);
+        assert_eq!(Some(7), line_of(err));
+        assert!(badges(&letters, &group, 0, Leftover::Partial).is_err());
+    }
+}
@@ -0,0 +1,215 @@
+use std::cmp::Reverse;
+use std::fmt::Write as _;
+
+use crate::solution::SolveResult;
+
+use super::items::{Alphabet, Items};
+use super::{groups, lines, Leftover, Rucksack};
+
+// An item found in more than one place, with how many of it each place holds
+#[derive(Debug, Clone, PartialEq)]
+pub struct Shared {
+    pub priority: usize,
+    pub counts: Vec<usize>,
+}
+
+// How often an item turned up in both compartments, over every rucksack
+#[derive(Debug, Clone, PartialEq)]
+pub struct Misplaced {
+    pub priority: usize,
+    pub rucksacks: usize,
+    // Copies in either compartment, summed over those rucksacks
+    pub copies: usize,
+}
+
+impl Misplaced {
+    // What the item adds to part 1's sum
+    pub fn priority_total(&self) -> usize {
+        self.priority * self.rucksacks
+    }
+}
+
+// Items in every rucksack of a group
+#[derive(Debug, Clone, PartialEq)]
+pub struct Group {
+    pub first_line: usize,
+    pub rucksacks: usize,
+    pub shared: Vec<Shared>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Report {
+    // To name the items
+    pub alphabet: Alphabet,
+    // Items in both compartments, for each rucksack in order
+    pub rucksacks: Vec<Vec<Shared>>,
+    pub size: usize,
+    pub groups: Vec<Group>,
+    // Most rucksacks first, then lowest priority
+    pub misplaced: Vec<Misplaced>,
+}
+
+// How many of each item, by priority
+fn counts(alphabet: &Alphabet, text: &str) -> Vec<usize> {
+    let mut res = vec![0; alphabet.size() + 1];
+    for priority in text.chars().filter_map(|item| alphabet.priority(item)) {
+        res[priority] += 1;
+    }
+    res
+}
+
+fn shared(alphabet: &Alphabet, common: Items, places: &[&str]) -> Vec<Shared> {
+    let counts: Vec<Vec<usize>> = places.iter().map(|place| counts(alphabet, place)).collect();
+    common
+        .priorities()
+        .map(|priority| Shared { priority, counts: counts.iter().map(|count| count[priority]).collect() })
+        .collect()
+}
+
+// Every item shared between compartments and within groups, however many
+// there are, where the puzzle expects exactly one
+pub fn report(alphabet: &Alphabet, rucksacks: &[Rucksack], size: usize, leftover: Leftover) -> SolveResult<Report> {
+    let mut by_item = vec![Misplaced { priority: 0, rucksacks: 0, copies: 0 }; alphabet.size() + 1];
+    let mut res = Report {
+        alphabet: alphabet.clone(),
+        rucksacks: Vec::with_capacity(rucksacks.len()),
+        size,
+        groups: Vec::new(),
+        misplaced: Vec::new(),
+    };
+    for rucksack in rucksacks {
+        let (left, right) = rucksack.compartments();
+        let found = shared(alphabet, rucksack.left.intersect(&rucksack.right), &[left, right]);
+        for item in &found {
+            by_item[item.priority].rucksacks += 1;
+            by_item[item.priority].copies += item.counts.iter().sum::<usize>();
+        }
+        res.rucksacks.push(found);
+    }
+
+    for (first_line, group) in groups(rucksacks, size, leftover)? {
+        let common = group.iter().fold(group[0].items(), |common, rucksack| common.intersect(&rucksack.items()));
+        let lines: Vec<&str> = group.iter().map(|rucksack| rucksack.line.as_str()).collect();
+        res.groups.push(Group { first_line, rucksacks: group.len(), shared: shared(alphabet, common, &lines) });
+    }
+
+    for (priority, mut item) in by_item.into_iter().enumerate() {
+        if item.rucksacks > 0 {
+            item.priority = priority;
+            res.misplaced.push(item);
+        }
+    }
+    res.misplaced.sort_by_key(|item| (Reverse(item.rucksacks), item.priority));
+    Ok(res)
+}
+
+// "p (1+2), L (1+1)", or "none"
+fn list(alphabet: &Alphabet, found: &[Shared]) -> String {
+    if found.is_empty() {
+        return String::from("none");
+    }
+    let items: Vec<String> = found
+        .iter()
+        .map(|item| {
+            let counts: Vec<String> = item.counts.iter().map(|count| count.to_string()).collect();
+            format!("{} ({})", alphabet.item(item.priority), counts.join("+"))
+        })
+        .collect();
+    items.join(", ")
+}
+
+impl Report {
+    // Lists every rucksack and group, then the `top` most misplaced items
+    pub fn text(&self, top: usize) -> String {
+        let mut res = String::new();
+        writeln!(res, "Shared between compartments, with copies in each:").unwrap();
+        for (idx, found) in self.rucksacks.iter().enumerate() {
+            writeln!(res, "  Line {}: {}", idx + 1, list(&self.alphabet, found)).unwrap();
+        }
+        writeln!(res, "Shared within groups of {}, with copies in each rucksack:", self.size).unwrap();
+        for group in &self.groups {
+            writeln!(res, "  {}: {}", lines(group.first_line, group.rucksacks), list(&self.alphabet, &group.shared)).unwrap();
+        }
+
+        let odd = self.rucksacks.iter().filter(|found| found.len() != 1).count();
+        let total: usize = self.misplaced.iter().map(Misplaced::priority_total).sum();
+        writeln!(res).unwrap();
+        writeln!(
+            res,
+            "{} rucksacks, {} without exactly one shared item; shared items' priorities add up to {}",
+            self.rucksacks.len(), odd, total
+        ).unwrap();
+        writeln!(res, "Most often misplaced:").unwrap();
+        writeln!(res, "  Item  Priority  Rucksacks  Copies  Priority total").unwrap();
+        for item in self.misplaced.iter().take(top) {
+            writeln!(
+                res,
+                "  {:>4}  {:>8}  {:>9}  {:>6}  {:>14}",
+                self.alphabet.item(item.priority), item.priority, item.rucksacks, item.copies, item.priority_total()
+            ).unwrap();
+        }
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day3::{parse_rucksacks, Day3, EXAMPLES};
+    use crate::solution::Solution;
+
+    #[test]
+    fn example_report() {
+        let rucksacks = Day3.parse(EXAMPLES[0].input).unwrap();
+        let res = report(&Alphabet::classic(), &rucksacks, 3, Leftover::Error).unwrap();
+        // p is once in each half of line 1, and the group's badge r is in
+        // its rucksacks 2, 3 and 1 times
+        assert_eq!(vec![Shared { priority: 16, counts: vec![1, 1] }], res.rucksacks[0]);
+        assert!(res.rucksacks.iter().all(|found| found.len() == 1));
+        assert_eq!(Group { first_line: 1, rucksacks: 3, shared: vec![Shared { priority: 18, counts: vec![2, 3, 1] }] }, res.groups[0]);
+
+        // Each shared item is in one rucksack, so the totals are part 1
+        let total: usize = res.misplaced.iter().map(Misplaced::priority_total).sum();
+        assert_eq!(157, total);
+        assert_eq!(Misplaced { priority: 16, rucksacks: 1, copies: 2 }, res.misplaced[0]);
+    }
+
+    #[test]
+    fn many_shared() {
+        let rucksacks = Day3.parse("abab\nabcc\nbbab\nxyzx\nxa\n").unwrap();
+        let letters = Alphabet::classic();
+        let res = report(&letters, &rucksacks, 2, Leftover::Partial).unwrap();
+        assert_eq!(2, res.rucksacks[0].len());
+        assert!(res.rucksacks[1].is_empty());
+        assert_eq!(vec![Shared { priority: 2, counts: vec![2, 1] }], res.rucksacks[2]);
+        assert_eq!(vec![1, 2], res.groups[0].shared.iter().map(|item| item.priority).collect::<Vec<_>>());
+        assert!(res.groups[1].shared.is_empty());
+        assert_eq!((5, 1), (res.groups[2].first_line, res.groups[2].rucksacks));
+
+        // b is misplaced twice, then a and x once each, a first on priority
+        let items: Vec<(usize, usize, usize)> = res.misplaced.iter().map(|item| (item.priority, item.rucksacks, item.copies)).collect();
+        assert_eq!(vec![(2, 2, 5), (1, 1, 2), (24, 1, 2)], items);
+
+        let text = res.text(2);
+        assert!(text.contains("  Line 1: a (1+1), b (1+1)"), "{}", text);
+        assert!(text.contains("  Line 2: none"), "{}", text);
+        assert!(text.contains("  Lines 3-4: none"), "{}", text);
+        assert!(text.contains("  Line 5: a (1), x (1)"), "{}", text);
+        assert!(text.contains("5 rucksacks, 3 without exactly one shared item; shared items' priorities add up to 29"), "{}", text);
+        assert!(!text.contains("    24"), "{}", text);
+
+        assert!(report(&letters, &rucksacks, 2, Leftover::Error).is_err());
+    }
+
+    #[test]
+    fn unicode_report() {
+        let alphabet = Alphabet::parse("☀ ☁ ☂").unwrap();
+        let rucksacks = parse_rucksacks(&alphabet, "☀☂☂☂\n☁☂☀☂\n").unwrap();
+        let res = report(&alphabet, &rucksacks, 2, Leftover::Error).unwrap();
+        assert_eq!(vec![Shared { priority: 3, counts: vec![1, 2] }], res.rucksacks[0]);
+        assert_eq!(vec![1, 3], res.groups[0].shared.iter().map(|item| item.priority).collect::<Vec<_>>());
+        let text = res.text(10);
+        assert!(text.contains("  Lines 1-2: ☀ (1+1), ☂ (3+2)"), "{}", text);
+        assert!(text.contains("     ☂         3          2       5               6"), "{}", text);
+    }
+}
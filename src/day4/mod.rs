@@ -1,8 +1,9 @@
-use std::{time::{Instant, Duration}, cmp::Ordering, fmt};
+use std::{cmp::Ordering, fmt};
 
-use crate::util::{get_filename, read_lines};
-
-const DEBUG: bool = false;
+use crate::error::{parse_number, Error};
+use crate::log::trace;
+use crate::solution::{examples, Answer, Example, Solution, SolveResult};
+use crate::util::{parse_lines, split_lines};
 // Every section in the camp has a unique ID
 // Every elf is assigned to a range of sections
 // However, some section assignments overlap
@@ -14,35 +15,48 @@ const DEBUG: bool = false;
 // Parse a range to get the top & bottom numbers
 // If (bottom A < bottom B && top A > top B) || (bottom B < bottom A && top B > top A)
 
-fn split_ranges(line: String) -> (String, String) {
-    let mut split = line.split(",");
-    let res1 = split.next().unwrap();
-    let res2 = split.next().unwrap();
-    (res1.to_string(), res2.to_string())
+pub struct Day4;
+
+fn split_ranges(line: &str) -> Result<(&str, &str), Error> {
+    match line.split_once(',') {
+        Some((res1, res2)) => Ok((res1, res2)),
+        None => Err(Error::parse(line, &line[line.len()..], "expected two ranges separated by ','")),
+    }
 }
-struct Range {
+pub struct Range {
     lower: isize,
     upper: isize
 }
 
 impl Range {
-    fn create(input: String) -> Range {
-        let mut split = input.split("-");
-        let lower = split.next().unwrap().to_string().parse().unwrap();
-        let upper = split.next().unwrap().to_string().parse().unwrap();
-        Range{
-            lower: lower,
-            upper: upper
+    // `input` is a slice of `line`, which is only needed for error reports
+    fn create(line: &str, input: &str) -> Result<Range, Error> {
+        let (lower, upper) = match input.split_once('-') {
+            Some(split) => split,
+            None => return Err(Error::parse(line, input, format!("expected a range like 2-4, found '{}'", input))),
+        };
+        let range = Range{
+            lower: parse_number(line, lower)?,
+            upper: parse_number(line, upper)?,
+        };
+        if range.lower > range.upper {
+            return Err(Error::parse(line, input, format!("range {} is backwards", range)));
         }
+        Ok(range)
+    }
+
+    fn parse_pair(line: &str) -> Result<(Range, Range), Error> {
+        let (range1, range2) = split_ranges(line)?;
+        Ok((Range::create(line, range1)?, Range::create(line, range2)?))
     }
 
     fn cmp(&self, other: &Range) -> Ordering {
         if self.lower < other.lower {
-            return Ordering::Less;
+            Ordering::Less
         } else if self.lower > other.lower {
-            return Ordering::Greater;
+            Ordering::Greater
         } else {
-            return Ordering::Equal;
+            Ordering::Equal
         }
     }
 
@@ -53,21 +67,21 @@ impl Range {
 
 fn complete_overlap(range1: &Range, range2: &Range) -> bool {
     let contains: bool;
-    match range1.cmp(&range2) {
+    match range1.cmp(range2) {
         Ordering::Less => {
-            contains = range1.contains(&range2);
-            if DEBUG && !contains{
-                println!("      {} contains {}: {}", &range1, &range2, contains)
+            contains = range1.contains(range2);
+            if !contains {
+                trace!("{} doesn't contain {}", range1, range2);
             }
         }
         Ordering::Greater => {
-            contains = range2.contains(&range1);
-            if DEBUG && !contains {
-                println!("      {} contains {}: {}", &range2, &range1, contains)
+            contains = range2.contains(range1);
+            if !contains {
+                trace!("{} doesn't contain {}", range2, range1);
             }
         }
         Ordering::Equal => {
-            contains = range2.contains(&range1) || range1.contains(&range2);
+            contains = range2.contains(range1) || range1.contains(range2);
         }
     }
     contains
@@ -82,13 +96,7 @@ fn any_overlap(range1: &Range, range2: &Range) -> bool {
     } else {
         contains = range2.upper >= range1.lower
     }
-    if DEBUG {
-        print!("    Comparing {} with {}. ", &range1, &range2);
-        match contains {
-            true => println!("Overlap"),
-            false => println!("No overlap"),
-        }
-    }
+    trace!("{} and {}: {}", range1, range2, if contains { "overlap" } else { "no overlap" });
     contains
 }
 
@@ -98,51 +106,64 @@ impl fmt::Display for Range {
     }
 }
 
-pub fn part1(input: super::Data) -> (usize, Duration) {
-    let now = Instant::now();
+fn part1(pairs: &[(Range, Range)]) -> usize {
     let mut res: usize = 0;
-
-    // Get lines
-    let lines = read_lines(get_filename("day4", input));
-    if let Ok(lines) = lines {
-        for line in lines {
-            if let Ok(line) = line {
-                let (range1, range2) = split_ranges(line);
-                let range1 = Range::create(range1);
-                let range2 = Range::create(range2);
-                if DEBUG {
-                    println!("    Comparing {} with {}.", &range1, &range2);
-                }
-                if complete_overlap(&range1, &range2) {
-                    res += 1;
-                }
-            }
+    for (range1, range2) in pairs {
+        trace!("comparing {} with {}", range1, range2);
+        if complete_overlap(range1, range2) {
+            res += 1;
         }
-
     }
-
-    (res, now.elapsed())
+    res
 }
 
-pub fn part2(input: super::Data) -> (usize, Duration) {
-    let now = Instant::now();
+fn part2(pairs: &[(Range, Range)]) -> usize {
     let mut res: usize = 0;
-    let lines = read_lines(get_filename("day4", input));
-    if let Ok(lines) = lines {
-        for line in lines {
-            if let Ok(line) = line {
-                let (range1, range2) = split_ranges(line);
-                let range1 = Range::create(range1);
-                let range2 = Range::create(range2);
-                if any_overlap(&range1, &range2) {
-                    res += 1;
-                }
-            }
+    for (range1, range2) in pairs {
+        if any_overlap(range1, range2) {
+            res += 1;
         }
+    }
+    res
+}
 
+examples! {
+    solution: Day4,
+    puzzle: {
+        input: "2-4,6-8
+2-3,4-5
+5-7,7-9
+2-8,3-7
+6-6,4-6
+2-6,4-8
+",
+        part1: "2",
+        part2: "4",
+    },
+    identical: {
+        input: "3-5,3-5\n1-1,2-2\n",
+        part1: "1",
+        part2: "1",
+    },
+}
+
+impl Solution for Day4 {
+    const DAY: usize = 4;
+    const TITLE: &'static str = "Camp Cleanup";
+    const EXAMPLES: &'static [Example] = EXAMPLES;
+    type Parsed = Vec<(Range, Range)>;
+
+    fn parse(&self, input: &str) -> SolveResult<Self::Parsed> {
+        parse_lines(&split_lines(input), Range::parse_pair)
+    }
+
+    fn part1(&self, pairs: &Self::Parsed) -> SolveResult<Answer> {
+        Ok(part1(pairs).into())
     }
 
-    (res, now.elapsed())
+    fn part2(&self, pairs: &Self::Parsed) -> SolveResult<Answer> {
+        Ok(part2(pairs).into())
+    }
 }
 
 #[cfg(test)]
@@ -150,14 +171,6 @@ pub fn part2(input: super::Data) -> (usize, Duration) {
 mod tests {
     use table_test::table_test;
 
-    use crate::Data;
-    #[test]
-    fn part1() {
-        let (res, _dur) = super::part1(Data::Test);
-        let want = 2;
-        assert_eq!(want, res);
-    }
-
     #[test]
     fn test_split_ranges() {
         let table = vec![
@@ -167,20 +180,37 @@ mod tests {
         ];
 
         for (validator, input, (expected1, expected2)) in table_test!(table) {
-            let actual = super::split_ranges(input.to_string());
+            let actual = super::split_ranges(input).unwrap();
 
             validator
-                .given(&format!("{}", input.to_string()))
+                .given(input)
                 .when("split_ranges")
                 .then(&format!("it should be ({}, {})", expected1, expected2))
-                .assert_eq((expected1.to_string(), expected2.to_string()), actual);
+                .assert_eq((expected1, expected2), actual);
         }
     }
 
     #[test]
-    fn part2() {
-        let (res, _dur) = super::part2(Data::Test);
-        let want = 4;
-        assert_eq!(want, res);
+    fn test_parse_pair_errors() {
+        let table = vec![
+            ("2-4;6-8", 8),
+            ("2-4,6", 5),
+            ("2-x,6-8", 3),
+            ("2-4,8-6", 5),
+            ("2-4,-8", 5),
+        ];
+
+        for (validator, input, expected) in table_test!(table) {
+            let column = match super::Range::parse_pair(input) {
+                Err(super::Error::Parse(err)) => err.column,
+                _ => 0,
+            };
+
+            validator
+                .given(input)
+                .when("Range::parse_pair")
+                .then(&format!("it should fail at column {}", expected))
+                .assert_eq(expected, column);
+        }
     }
 }
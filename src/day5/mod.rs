@@ -1,175 +1,239 @@
-const DEBUG: bool = false;
-// use super::Data;
-use std::time::{Instant, Duration}; 
-use crate::util::{get_filename, read_lines};
-use std::io::{self, BufReader};
-use std::fs::File;
+use crate::error::{parse_number, Error};
+use crate::log::{debug, trace};
+use crate::solution::{examples, Answer, Example, Solution, SolveResult};
+use crate::util::split_lines;
 use regex::Regex;
 
-#[derive(Debug, PartialEq)]
+pub struct Day5;
+
+#[derive(Debug, Clone, PartialEq)]
 struct Hanoi {
     stacks: Vec<Vec<char>>,
     instructions: Vec<String>
 }
 
 impl Hanoi {
-    fn create(mut file: io::Lines<BufReader<File>>) -> Hanoi {
+    fn create<I: Iterator<Item = String>>(mut file: I) -> Result<Hanoi, Error> {
         let mut lines = Vec::new();
         let mut hanoi = Hanoi{
             stacks: Vec::new(),
             instructions: Vec::new()
         };
         loop {
-            let line = file.next().unwrap().unwrap();
-            if line == "" {
-                break;
-            } else {
-                lines.push(line);
+            match file.next() {
+                Some(line) if line.is_empty() => break,
+                Some(line) => lines.push(line),
+                None => {
+                    let last = lines.last().map(String::as_str).unwrap_or("");
+                    return Err(Error::parse(last, &last[last.len()..], "expected a blank line after the stack drawing")
+                        .at_line(lines.len().max(1)));
+                }
             }
         }
-        for _ in 0..Hanoi::no_stacks(lines.pop().unwrap()) {
+        let stack_line_no = lines.len();
+        let stack_line = match lines.pop() {
+            Some(line) => line,
+            None => return Err(Error::parse("", "", "expected a stack drawing before the blank line").at_line(1)),
+        };
+        let no_stacks = Hanoi::no_stacks(stack_line).map_err(|err| err.at_line(stack_line_no))?;
+        for _ in 0..no_stacks {
             hanoi.stacks.push(Vec::new());
         }
-        loop {
-            let line = lines.pop();
-            if let None = line {
-                break;
-            }
-            hanoi.parse_row(line.unwrap());
+        // Bottom row first
+        for (idx, line) in lines.iter().enumerate().rev() {
+            hanoi.parse_row(line).map_err(|err| err.at_line(idx + 1))?;
         }
-        loop {
-            let line = file.next();
-            if let Some(line) = line {
-                if let Ok(line) = line {
-                    hanoi.instructions.push(line);
-                }
-            } else {
-                break;
-            }
+        for line in file {
+            hanoi.instructions.push(line);
         }
-        hanoi
+        Ok(hanoi)
     }
 
     fn get_tops(&self) -> String {
         let mut res: String = String::new();
         for n in 0..self.stacks.len() {
-            if self.stacks[n].len() == 0 {
+            if self.stacks[n].is_empty() {
                 res.push(' ');
             } else {
-                res.push(self.stacks[n].last().unwrap().clone());
+                res.push(*self.stacks[n].last().unwrap());
             }
         }
 
         res
     }
 
-    fn parse_row(&mut self, row: String) {
-        let mut chars = row.chars();
-        let mut stack: usize = 0;
-        let mut curr_char: char;
-        // Get first character
-        let next_char = chars.next();
-        if let None = next_char {
-            return;
-        }
-        loop {
-            let next_char = chars.next();
-            if let None = next_char {
-                return;
+    // Crate letters are every fourth character, starting from the second
+    fn parse_row(&mut self, row: &str) -> Result<(), Error> {
+        for (stack, (at, letter)) in row.char_indices().skip(1).step_by(4).enumerate() {
+            if letter == ' ' {
+                continue;
             }
-            curr_char = next_char.unwrap();
-            if curr_char != ' ' {
-                self.stacks[stack].push(curr_char);
-            }
-            stack += 1;
-            if stack >= self.stacks.len() {
-                return;
-            }
-            for _ in 0..3 {
-                let chomp = chars.next();
-                if let None = chomp {
-                    return;
+            match self.stacks.get_mut(stack) {
+                Some(crates) => crates.push(letter),
+                None => {
+                    let message = format!("crate in column {}, but there are only {} stacks", stack+1, self.stacks.len());
+                    return Err(Error::parse(row, &row[at..at + letter.len_utf8()], message));
                 }
             }
         }
+        Ok(())
     }
 
-    fn move_stack(&mut self, no: usize, curr: usize, dest: usize) {
-        if DEBUG {
-            println!("Moving {} items from stack {} to stack {}.", no, curr+1, dest+1);
+    // Check a move can be made before touching any stacks
+    fn check_move(&self, no: usize, curr: usize, dest: usize) -> Result<(), String> {
+        for stack in [curr, dest] {
+            if stack >= self.stacks.len() {
+                return Err(format!("there is no stack {} (only {} stacks)", stack+1, self.stacks.len()));
+            }
+        }
+        if self.stacks[curr].len() < no {
+            return Err(format!("stack {} only holds {} crates, can't move {}", curr+1, self.stacks[curr].len(), no));
         }
+        Ok(())
+    }
+
+    fn move_stack(&mut self, no: usize, curr: usize, dest: usize) -> Result<(), String> {
+        self.check_move(no, curr, dest)?;
+        debug!("moving {} crates one at a time from stack {} to stack {}", no, curr+1, dest+1);
         let mut move_stack = Vec::new();
         for _ in 0..no {
             move_stack.push(self.stacks[curr].pop().unwrap());
         }
-        if DEBUG {
-            println!("Stack to move (reversed): {:?}", &move_stack);
-        }
+        trace!("crates to move (reversed): {:?}", &move_stack);
         move_stack.reverse();
         for _ in 0..move_stack.len() {
             self.stacks[dest].push(move_stack.pop().unwrap());
         }
+        Ok(())
     }
 
-    fn move_stack_multiple(&mut self, no: usize, curr: usize, dest: usize) {
-        if DEBUG {
-            println!("Moving {} items from stack {} to stack {}.", no, curr+1, dest+1);
-        }
+    fn move_stack_multiple(&mut self, no: usize, curr: usize, dest: usize) -> Result<(), String> {
+        self.check_move(no, curr, dest)?;
+        debug!("moving {} crates together from stack {} to stack {}", no, curr+1, dest+1);
         let mut move_stack = Vec::new();
         for _ in 0..no {
             move_stack.push(self.stacks[curr].pop().unwrap());
         }
-        if DEBUG{
-            println!("Stack to move: {:?}", &move_stack);
-        }
+        trace!("crates to move: {:?}", &move_stack);
         for _ in 0..move_stack.len() {
             self.stacks[dest].push(move_stack.pop().unwrap());
         }
+        Ok(())
     }
 
-    fn no_stacks(stackline: String) -> isize {
-        let mut res: isize = 0;
-        for letter in stackline.chars() {
-            if letter != ' ' {
-                res += 1;
-            }
+    // The label line numbers the stacks, so it's as many labels as stacks
+    fn no_stacks(stackline: String) -> Result<usize, Error> {
+        let mut res = 0;
+        for label in stackline.split_whitespace() {
+            parse_number::<usize>(&stackline, label)?;
+            res += 1;
         }
-        res
+        if res == 0 {
+            return Err(Error::parse(&stackline, &stackline, "expected stack numbers under the drawing"));
+        }
+        Ok(res)
     }
 
-    fn parse_instruction(instruction: String) -> (usize, usize, usize) {
-        let re = Regex::new(r"move (\d+) from (\d+) to (\d+)").unwrap();
-        let caps = re.captures(instruction.as_str()).unwrap();
-        (
-            caps.get(1).unwrap().as_str().parse::<usize>().unwrap(),
-            caps.get(2).unwrap().as_str().parse::<usize>().unwrap()-1,
-            caps.get(3).unwrap().as_str().parse::<usize>().unwrap()-1
-        )
+    fn parse_instruction(instruction: String) -> Result<(usize, usize, usize), Error> {
+        let re = Regex::new(r"^move (\d+) from (\d+) to (\d+)$").unwrap();
+        let caps = match re.captures(instruction.as_str()) {
+            Some(caps) => caps,
+            None => return Err(Error::parse(&instruction, &instruction, "expected 'move N from A to B'")),
+        };
+        let stack = |idx: usize| -> Result<usize, Error> {
+            let field = &instruction[caps.get(idx).unwrap().range()];
+            match parse_number::<usize>(&instruction, field)? {
+                0 => Err(Error::parse(&instruction, field, "stacks are numbered from 1")),
+                n => Ok(n-1),
+            }
+        };
+        Ok((
+            parse_number(&instruction, &instruction[caps.get(1).unwrap().range()])?,
+            stack(2)?,
+            stack(3)?
+        ))
+    }
+}
+
+// The starting stacks and the parsed moves, shared by both parts
+#[derive(Debug)]
+pub struct Procedure {
+    hanoi: Hanoi,
+    moves: Vec<(usize, usize, usize)>,
+    // Line number of the first instruction, for error reports
+    first_line: usize,
+}
+
+impl Procedure {
+    fn create(lines: &[String]) -> Result<Procedure, Error> {
+        let hanoi = Hanoi::create(lines.iter().cloned())?;
+        // Instructions are the last lines of the input
+        let first_line = lines.len() - hanoi.instructions.len() + 1;
+        let mut moves = Vec::new();
+        for (idx, instruction) in hanoi.instructions.iter().enumerate() {
+            let step = Hanoi::parse_instruction(instruction.clone()).map_err(|err| err.at_line(first_line + idx))?;
+            moves.push(step);
+        }
+        Ok(Procedure { hanoi, moves, first_line })
     }
 }
 
-pub fn part1(input: crate::Data) -> (String, Duration) {
-    let now = Instant::now();
-    let lines = read_lines(get_filename("day5", input)).unwrap();
-    let mut hanoi = Hanoi::create(lines);
-    for instruction in hanoi.instructions.clone(){
-        let (no, curr, dest) = Hanoi::parse_instruction(instruction);
-        hanoi.move_stack(no, curr, dest);
+// Moving crates changes the stacks, so each part works on its own copy
+fn rearrange(procedure: &Procedure, crane: fn(&mut Hanoi, usize, usize, usize) -> Result<(), String>) -> Result<String, Error> {
+    let mut hanoi = procedure.hanoi.clone();
+    for (idx, &(no, curr, dest)) in procedure.moves.iter().enumerate() {
+        crane(&mut hanoi, no, curr, dest).map_err(|msg| {
+            let instruction = &procedure.hanoi.instructions[idx];
+            Error::parse(instruction, instruction, msg).at_line(procedure.first_line + idx)
+        })?;
     }
 
-    (hanoi.get_tops(), now.elapsed())
+    Ok(hanoi.get_tops())
 }
 
-pub fn part2(input: crate::Data) -> (String, Duration) {
-    let now = Instant::now();
-    let lines = read_lines(get_filename("day5", input)).unwrap();
-    let mut hanoi = Hanoi::create(lines);
-    for instruction in hanoi.instructions.clone(){
-        let (no, curr, dest) = Hanoi::parse_instruction(instruction);
-        hanoi.move_stack_multiple(no, curr, dest);
+fn part1(procedure: &Procedure) -> Result<String, Error> {
+    rearrange(procedure, Hanoi::move_stack)
+}
+
+fn part2(procedure: &Procedure) -> Result<String, Error> {
+    rearrange(procedure, Hanoi::move_stack_multiple)
+}
+
+examples! {
+    solution: Day5,
+    puzzle: {
+        input: "    [D]
+[N] [C]
+[Z] [M] [P]
+ 1   2   3
+
+move 1 from 2 to 1
+move 3 from 1 to 3
+move 2 from 2 to 1
+move 1 from 1 to 2
+",
+        part1: "CMZ",
+        part2: "MCD",
+    },
+}
+
+impl Solution for Day5 {
+    const DAY: usize = 5;
+    const TITLE: &'static str = "Supply Stacks";
+    const EXAMPLES: &'static [Example] = EXAMPLES;
+    type Parsed = Procedure;
+
+    fn parse(&self, input: &str) -> SolveResult<Self::Parsed> {
+        Procedure::create(&split_lines(input))
+    }
+
+    fn part1(&self, procedure: &Self::Parsed) -> SolveResult<Answer> {
+        Ok(part1(procedure)?.into())
     }
 
-    (hanoi.get_tops(), now.elapsed())
+    fn part2(&self, procedure: &Self::Parsed) -> SolveResult<Answer> {
+        Ok(part2(procedure)?.into())
+    }
 }
 
 #[cfg(test)]
@@ -177,20 +241,20 @@ pub fn part2(input: crate::Data) -> (String, Duration) {
 mod tests {
     use table_test::table_test;
     use super::*;
-    use crate::Data;
 
     #[test]
     fn test_get_no_stacks() {
-        let table: Vec<(&str, isize)> = vec![
+        let table: Vec<(&str, usize)> = vec![
             ("1 2 3", 3),
             (" 1   2   3   4   5   6   7   8   9 ", 9),
+            (" 1   2   3   4   5   6   7   8   9  10 ", 10),
         ];
 
         for (validator, input, expected) in table_test![table] {
-            let actual = Hanoi::no_stacks(input.to_string());
+            let actual = Hanoi::no_stacks(input.to_string()).unwrap();
 
             validator
-                .given(&format!("{}", input))
+                .given(input)
                 .when("no_stacks")
                 .then(&format!("it should be {}", expected))
                 .assert_eq(expected, actual);
@@ -199,8 +263,8 @@ mod tests {
 
     #[test]
     fn create_hanoi() {
-        let lines = read_lines(get_filename("day5", Data::Test)).unwrap();
-        let actual = Hanoi::create(lines);
+        let lines = split_lines(EXAMPLES[0].input);
+        let actual = Hanoi::create(lines.into_iter()).unwrap();
         let expected = Hanoi {
             stacks: vec![
                 vec!['Z', 'N'],
@@ -220,8 +284,8 @@ mod tests {
 
     #[test]
     fn move_stack() {
-        let lines = read_lines(get_filename("day5", Data::Test)).unwrap();
-        let mut hanoi = Hanoi::create(lines);
+        let lines = split_lines(EXAMPLES[0].input);
+        let mut hanoi = Hanoi::create(lines.into_iter()).unwrap();
         let mut expected = Hanoi {
             stacks: vec![
                 vec!['Z', 'N'],
@@ -236,12 +300,12 @@ mod tests {
             ]
         };
         assert_eq!(expected, hanoi);
-        hanoi.move_stack(1, 1, 0);
+        hanoi.move_stack(1, 1, 0).unwrap();
         expected.stacks[0] = vec!['Z', 'N', 'D'];
         expected.stacks[1] = vec!['M', 'C'];
         assert_eq!(expected, hanoi);
 
-        hanoi.move_stack(3, 0, 2);
+        hanoi.move_stack(3, 0, 2).unwrap();
         expected.stacks[0] = Vec::new();
         expected.stacks[2] = vec!['P', 'D', 'N', 'Z'];
         assert_eq!(expected, hanoi);
@@ -257,10 +321,10 @@ mod tests {
         ];
 
         for (validator, input, (expected1, expected2, expected3)) in table_test!(table) {
-            let (actual1, actual2, actual3) = Hanoi::parse_instruction(input.to_string()); 
+            let (actual1, actual2, actual3) = Hanoi::parse_instruction(input.to_string()).unwrap();
 
             validator
-                .given(&format!("{}", input))
+                .given(input)
                 .when("Hanoi::create")
                 .then(&format!("should be ({}, {}, {})", expected1, expected2, expected3))
                 .assert_eq(expected1, actual1)
@@ -270,10 +334,66 @@ mod tests {
     }
 
     #[test]
-    fn part1() {
-        let (res, _dur) = super::part1(Data::Test);
-        let expected = String::from("CMZ");
+    fn parse_instruction_errors() {
+        let table = vec![
+            ("move 1 from 2", 1),
+            ("move 1 from 0 to 2", 13),
+            ("move 99999999999999999999 from 1 to 2", 6),
+        ];
+
+        for (validator, input, expected) in table_test!(table) {
+            let column = match Hanoi::parse_instruction(input.to_string()) {
+                Err(Error::Parse(err)) => err.column,
+                _ => 0,
+            };
 
-        assert_eq!(expected, res);
+            validator
+                .given(input)
+                .when("Hanoi::parse_instruction")
+                .then(&format!("it should fail at column {}", expected))
+                .assert_eq(expected, column);
+        }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn bad_moves() {
+        let mut hanoi = Hanoi {
+            stacks: vec![vec!['A'], vec![]],
+            instructions: Vec::new(),
+        };
+        assert!(hanoi.move_stack(2, 0, 1).is_err());
+        assert!(hanoi.move_stack(1, 0, 2).is_err());
+        assert!(hanoi.move_stack_multiple(1, 1, 0).is_err());
+        assert_eq!(vec![vec!['A'], vec![]], hanoi.stacks);
+    }
+
+    #[test]
+    fn create_without_instructions() {
+        let lines = vec![String::from("[A]"), String::from(" 1 ")];
+        assert!(Hanoi::create(lines.into_iter()).is_err());
+    }
+
+    #[test]
+    fn create_bad_drawing() {
+        let table = vec![
+            // No stack numbers under the crates
+            ("[A]\n   \n\nmove 1 from 1 to 1", (2, 1)),
+            (" x \n\nmove 1 from 1 to 1", (1, 2)),
+            // A crate past the last stack
+            ("[A] [B]\n 1 \n\nmove 1 from 1 to 1", (1, 6)),
+        ];
+
+        for (validator, input, expected) in table_test!(table) {
+            let actual = match Hanoi::create(split_lines(input).into_iter()) {
+                Err(Error::Parse(err)) => (err.line.unwrap_or(0), err.column),
+                _ => (0, 0),
+            };
+
+            validator
+                .given(input)
+                .when("Hanoi::create")
+                .then(&format!("it should fail at {:?}", expected))
+                .assert_eq(expected, actual);
+        }
+    }
+}
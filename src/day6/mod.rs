@@ -1,37 +1,10 @@
-use std::time::{Instant, Duration};
-use std::{fmt, fs};
-use std::error;
+use std::fmt;
 
-const DEBUG: bool = false;
+use crate::error::Error;
+use crate::log::trace;
+use crate::solution::{examples, Answer, Example, Solution, SolveResult};
 
-#[derive(Debug)]
-pub enum Day6Error {
-    IO(std::io::Error),
-}
-
-impl fmt::Display for Day6Error {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            Day6Error::IO(..) => write!(f, "there was an IO error"),
-        }
-    }
-}
-
-impl error::Error for Day6Error {
-    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
-        match *self {
-            Day6Error::IO(ref e) => Some(e),
-        }
-    }
-}
-
-impl From<std::io::Error> for Day6Error {
-    fn from(err: std::io::Error) -> Day6Error {
-        Day6Error::IO(err)
-    }
-}
-
-use crate::util::get_filename;
+pub struct Day6;
 
 struct Tape {
     length: usize,
@@ -61,9 +34,7 @@ impl Tape {
         let mut existing  = Vec::new();
         for char in &self.content {
             if existing.contains(char) {
-                if DEBUG {
-                    println!("  {} repeated", char);
-                }
+                trace!("{} repeated", char);
                 return true;
             }
             existing.push(*char);
@@ -79,69 +50,131 @@ impl fmt::Display for Tape {
     }
 }
 
-// Part 1 - Single row input, random-seeming letters.
-// Take input, find index of first character of actual message
-// Message starts after 4 consecutive different characters
-pub fn part1(input: crate::Data) -> Result<(usize, Duration), Day6Error> {
-    let now = Instant::now();
-    let filename = get_filename("day6", input);
-    let message = fs::read_to_string(filename).unwrap();
-    let mut message = message.chars();
-
+// Index just past the first run of `length` distinct characters
+fn find_marker(message: &str, length: usize) -> Result<usize, Error> {
+    let mut chars = message.chars();
     let mut tape = Tape{
-        length: 4,
+        length,
         idx: 0,
         content: Vec::new(),
     };
 
-    for _ in 0..4 {
-        tape.chomp(message.next().unwrap());
+    for _ in 0..length {
+        match chars.next() {
+            Some(char) => tape.chomp(char),
+            None => return Err(Error::parse(message, &message[message.len()..],
+                format!("message is shorter than a {} character marker", length)).at_line(1)),
+        }
     }
-    if DEBUG {
-        println!("{}", tape);
+    trace!("{}", tape);
+    if !tape.repeated_char() {
+        return Ok(tape.idx);
     }
 
-    for char in message {
+    for char in chars {
         tape.chomp(char);
-        if DEBUG{
-            println!("{}", tape);
-        }
+        trace!("{}", tape);
         if !tape.repeated_char() {
-            break;
+            return Ok(tape.idx);
         }
     }
 
-    Ok((tape.idx, now.elapsed()))
+    Err(Error::solve(format!("no run of {} distinct characters in the message", length)))
 }
 
-pub fn part2(input: crate::Data) -> Result<(usize, Duration), Day6Error> {
-    let now = Instant::now();
-    let filename = get_filename("day6", input);
-    let message = fs::read_to_string(filename).unwrap();
-    let mut message = message.chars();
-    let mut tape = Tape{
-        length: 14,
-        idx: 0,
-        content: Vec::new(),
-    };
+// Part 1 - Single row input, random-seeming letters.
+// Take input, find index of first character of actual message
+// Message starts after 4 consecutive different characters
+fn part1(message: &str) -> Result<usize, Error> {
+    find_marker(message, 4)
+}
+
+// Part 2 - Same, but the message starts after 14 different characters
+fn part2(message: &str) -> Result<usize, Error> {
+    find_marker(message, 14)
+}
+
+examples! {
+    solution: Day6,
+    puzzle: {
+        input: "mjqjpqmgbljsphdztnvjfqwrcgsmlb\n",
+        part1: "7",
+        part2: "19",
+    },
+    second: {
+        input: "bvwbjplbgvbhsrlpgdmjqwftvncz\n",
+        part1: "5",
+        part2: "23",
+    },
+    third: {
+        input: "nppdvjthqldpwncqszvftbrmjlhg\n",
+        part1: "6",
+        part2: "23",
+    },
+    fourth: {
+        input: "nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg\n",
+        part1: "10",
+        part2: "29",
+    },
+    fifth: {
+        input: "zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw\n",
+        part1: "11",
+        part2: "26",
+    },
+}
+
+impl Solution for Day6 {
+    const DAY: usize = 6;
+    const TITLE: &'static str = "Tuning Trouble";
+    const EXAMPLES: &'static [Example] = EXAMPLES;
+    type Parsed = String;
 
-    // Consume first 14 chars
-    for _ in 0..14 {
-        tape.chomp(message.next().unwrap());
+    fn parse(&self, input: &str) -> SolveResult<Self::Parsed> {
+        let message = input.trim_end_matches(['\r', '\n']);
+        if let Some((_, extra)) = message.split_once('\n') {
+            return Err(Error::parse(extra, extra, "expected the message on a single line").at_line(2));
+        }
+        Ok(message.to_string())
     }
-    if DEBUG {
-        println!("{}", tape);
+
+    fn part1(&self, message: &Self::Parsed) -> SolveResult<Answer> {
+        Ok(part1(message)?.into())
     }
 
-    for char in message {
-        tape.chomp(char);
-        if DEBUG{
-            println!("{}", tape);
-        }
-        if !tape.repeated_char() {
-            break;
+    fn part2(&self, message: &Self::Parsed) -> SolveResult<Answer> {
+        Ok(part2(message)?.into())
+    }
+}
+
+#[cfg(test)]
+#[macro_use]
+mod tests {
+    use table_test::table_test;
+
+    #[test]
+    fn find_marker() {
+        let table = vec![
+            (("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 4), 7),
+            (("bvwbjplbgvbhsrlpgdmjqwftvncz", 4), 5),
+            (("abcdefg", 4), 4),
+            (("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 14), 19),
+            (("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", 14), 29),
+        ];
+
+        for (validator, (input, length), expected) in table_test!(table) {
+            let actual = super::find_marker(input, length).unwrap();
+
+            validator
+                .given(&format!("{}, length {}", input, length))
+                .when("find_marker")
+                .then(&format!("it should be {}", expected))
+                .assert_eq(expected, actual);
         }
     }
 
-    Ok((tape.idx, now.elapsed()))
-}
\ No newline at end of file
+    #[test]
+    fn find_marker_errors() {
+        assert!(super::find_marker("abc", 4).is_err());
+        assert!(super::find_marker("aaaaaaaa", 4).is_err());
+    }
+}
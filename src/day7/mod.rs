@@ -1,16 +1,14 @@
-use std::{time::{Instant, Duration}, fmt};
+use std::fmt;
 use std::rc::Rc;
 use std::cell::RefCell;
-use crate::util::{get_filename, read_lines};
+use crate::error::{parse_number, Error};
+use crate::log::{debug, trace};
+use crate::solution::{examples, Answer, Example, Solution, SolveResult};
+use crate::util::split_lines;
 extern crate separator;
 use separator::Separatable;
 
-const DEBUG: bool = false;
-
-#[derive(Debug, Clone)]
-pub enum Day7Error {
-    CDError(String)
-}
+pub struct Day7;
 
 #[derive(Debug, Clone)]
 enum FSObjectType {
@@ -25,7 +23,7 @@ enum Command {
 }
 
 #[derive(Debug, Clone)]
-struct Dir {
+pub struct Dir {
     name: String,
     contents: Vec<Rc<RefCell<Dir>>>,
     obj: FSObjectType,
@@ -65,13 +63,13 @@ impl Dir {
         self.parent = Some(parent);
     }
 
-    fn cd(&self, command: &str) -> Result<Rc<RefCell<Dir>>, Day7Error> {
+    fn cd(&self, command: &str) -> Result<Rc<RefCell<Dir>>, String> {
         match command {
             "/" => {
                 if self.name == "/" {
-                    return Err(Day7Error::CDError(String::from("Already in root dir")));
+                    return Err(String::from("Already in root dir"));
                 }
-                let mut curr_dir = self.cd("..").unwrap();
+                let mut curr_dir = self.cd("..")?;
                 loop {
                     let next_dir = curr_dir.borrow().cd("..");
                     match next_dir {
@@ -83,8 +81,8 @@ impl Dir {
             ".." => {
                 let new_dir = self.parent.as_ref();
                 match new_dir {
-                    None => return Err(Day7Error::CDError("Directory .. does not exist".to_string())),
-                    Some(new_dir) => return Ok(Rc::clone(new_dir)),
+                    None => Err("Directory .. does not exist".to_string()),
+                    Some(new_dir) => Ok(Rc::clone(new_dir)),
                 }
             }
             _ => {
@@ -93,20 +91,19 @@ impl Dir {
                         return Ok(Rc::clone(item));
                     }
                 }
-                return Err(Day7Error::CDError(format!("Directory {} does not exist", command)));
+                Err(format!("Directory {} does not exist", command))
             }
         }
     }
 
-    #[allow(dead_code)]
     fn get_size(&mut self) -> usize {
         match self.size{
-            Some(size) => return size,
+            Some(size) => size,
             None => {
                 match self.obj {
                     FSObjectType::File(size) => {
                         self.size = Some(size);
-                        return size;
+                        size
                     }
                     FSObjectType::Dir => {
                         let mut res = 0;
@@ -114,7 +111,7 @@ impl Dir {
                             res += item.borrow_mut().get_size();
                         }
                         self.size = Some(res);
-                        return res;
+                        res
                     }
                 }
             }
@@ -142,10 +139,9 @@ impl Dir {
         res
     }
 
-    fn part_1_sizes(&mut self, depth: usize) -> usize {
-        match self.obj {
-            FSObjectType::File(_) => return 0,
-            _ => {}
+    fn part_1_sizes(&mut self) -> usize {
+        if let FSObjectType::File(_) = self.obj {
+            return 0;
         }
         let mut total = 0;
         let size = self.get_size();
@@ -153,36 +149,29 @@ impl Dir {
             total += size;
         }
         for item in &self.contents {
-            total += item.borrow_mut().part_1_sizes(depth+1);
+            total += item.borrow_mut().part_1_sizes();
         }
-        return total;
+        total
     }
 
     fn delete_size(&mut self, target: usize, smallest: usize, depth: usize) -> usize {
-        match self.obj {
-            FSObjectType::File(_) => { return smallest; }
-            _ => {}
+        if let FSObjectType::File(_) = self.obj {
+            return smallest;
         }
         let our_size = self.get_size();
         let mut padding = String::from("");
         for _ in 0..=depth {
-            padding.push_str(" ");
-        }
-        if DEBUG {
-            print!("{}Checking dir {}, size {}... ",padding, self.name, our_size.separated_string());
+            padding.push(' ');
         }
+        trace!("{}checking dir {}, size {}", padding, self.name, our_size.separated_string());
         let mut res = smallest;
         if our_size < target {
-            if DEBUG {
-                println!("unable to free {}", target.separated_string());
-            }
+            trace!("{}unable to free {}", padding, target.separated_string());
             // If our own size is smaller than the target, neither we nor our children can help.
             return smallest;
         } else if our_size < smallest {
             // If our own size is enough to free up space but smaller than the current smallest, update it.
-            if DEBUG {
-                println!("Dir {} is size {}, smaller than {}", self.name, our_size.separated_string(), smallest.separated_string());
-            }
+            trace!("{}dir {} is size {}, smaller than {}", padding, self.name, our_size.separated_string(), smallest.separated_string());
             res = our_size;
         }
         // If we're big enough to help but still bigger than the smallest, maybe our children could help.
@@ -191,9 +180,7 @@ impl Dir {
             let child_size = item.borrow_mut().delete_size(target, res, depth+1);
             child_sizes.push(child_size);
             if child_size != res {
-                if DEBUG {
-                    println!("{}  Child {} is size {}",padding, item.borrow().name, child_size.separated_string());
-                }
+                trace!("{}  child {} is size {}", padding, item.borrow().name, child_size.separated_string());
             }
         }
         let mut lowest = res;
@@ -222,22 +209,44 @@ fn child_file(parent: Rc<RefCell<Dir>>, name: &str, size: usize) {
     child.borrow_mut().set_parent(parent);
 }
 
-fn parse_cmd(line: String) -> Command {
-    let mut words = line.split(" ");
+fn parse_cmd(line: &str) -> Result<Command, Error> {
+    let mut words = line.split(' ');
     words.next();
-    let cmd = words.next().unwrap();
-    match cmd {
-        "ls" => return Command::Ls,
+    let cmd = match words.next() {
+        Some(cmd) if !cmd.is_empty() => cmd,
+        _ => return Err(Error::parse(line, &line[line.len()..], "expected a command after '$'")),
+    };
+    let cmd = match cmd {
+        "ls" => Command::Ls,
         "cd" => {
-            let dir = words.next().unwrap();
-            return Command::Cd(dir.to_owned());
+            match words.next() {
+                Some(dir) if !dir.is_empty() => Command::Cd(dir.to_owned()),
+                _ => return Err(Error::parse(line, &line[line.len()..], "expected a directory to cd into")),
+            }
         }
-        _ => return Command::Noop,
+        _ => return Err(Error::parse(line, cmd, format!("unknown command '{}'", cmd))),
+    };
+    match words.next() {
+        Some(extra) => Err(Error::parse(line, extra, "unexpected argument")),
+        None => Ok(cmd),
     }
 }
 
-pub fn part1(input: crate::Data) -> Result<(usize, Duration), Day7Error> {
-    let now = Instant::now();
+// One line of `ls` output: `dir <name>` or `<size> <name>`
+fn parse_listing(line: &str) -> Result<(Option<usize>, &str), Error> {
+    let (file_type, name) = match line.split_once(' ') {
+        Some(split) => split,
+        None => return Err(Error::parse(line, &line[line.len()..], "expected '<size|dir> <name>'")),
+    };
+    if file_type == "dir" {
+        Ok((None, name))
+    } else {
+        Ok((Some(parse_number(line, file_type)?), name))
+    }
+}
+
+// Replays the terminal transcript to build the filesystem tree, returning the root
+fn build_tree(lines: &[String]) -> Result<Rc<RefCell<Dir>>, Error> {
     let fs = Dir {
         name: String::from("/"),
         contents: Vec::new(),
@@ -249,130 +258,169 @@ pub fn part1(input: crate::Data) -> Result<(usize, Duration), Day7Error> {
     let root = Rc::new(RefCell::new(fs));
     let mut current = Rc::clone(&root);
     let mut cmd = Command::Noop;
-    let lines = read_lines(get_filename("day7", input)).unwrap();
-    for line in lines {
-        if let Ok(line) = line {
-            if line.starts_with("$") {
-                cmd = parse_cmd(line);
-                match cmd {
-                    Command::Cd(ref dest) => {
-                        let new_dir = current.borrow().cd(dest);
-                        match new_dir {
-                            Err(new_dir) => {
-                                if current.borrow().name == "/" {
-                                    continue;
-                                } else {
-                                    return Err(new_dir);
-                                }
-                            }
-                            Ok(new_dir) => {
-                                current = new_dir;
-                            }
-                        }
+    for (idx, line) in lines.iter().enumerate() {
+        if line.starts_with('$') {
+            cmd = parse_cmd(line).map_err(|err| err.at_line(idx + 1))?;
+            if let Command::Cd(ref dest) = cmd {
+                let new_dir = current.borrow().cd(dest);
+                match new_dir {
+                    // cd / only fails when already at the root
+                    Err(_) if dest == "/" => continue,
+                    Err(msg) => {
+                        let dest = &line[line.len() - dest.len()..];
+                        return Err(Error::parse(line, dest, msg).at_line(idx + 1));
                     }
-                    _ => {}
-                }
-            } else {
-                match cmd {
-                    Command::Noop => { continue; }
-                    Command::Ls => {
-                        let mut words = line.split(" ");
-                        let file_type = words.next().unwrap();
-                        let name = words.next().unwrap();
-                        if file_type == "dir" {
-                            child_dir(Rc::clone(&current), name);
-                        } else {
-                            let size = file_type.parse::<usize>().unwrap();
-                            child_file(Rc::clone(&current), name, size);
-                        }
+                    Ok(new_dir) => {
+                        current = new_dir;
                     }
-                    Command::Cd(_) => {
+                }
+            }
+        } else {
+            match cmd {
+                Command::Noop => {
+                    return Err(Error::parse(line, line, "output before any command").at_line(idx + 1));
+                }
+                Command::Ls => {
+                    match parse_listing(line).map_err(|err| err.at_line(idx + 1))? {
+                        (None, name) => child_dir(Rc::clone(&current), name),
+                        (Some(size), name) => child_file(Rc::clone(&current), name, size),
                     }
                 }
+                Command::Cd(_) => {
+                    return Err(Error::parse(line, line, "cd doesn't produce output").at_line(idx + 1));
+                }
             }
         }
     }
 
-    let new_dir = current.borrow().cd("/")?;
-    current = new_dir;
-    let size = current.borrow_mut().part_1_sizes(0);
+    Ok(root)
+}
 
-    Ok((size, now.elapsed()))
+fn part1(root: &Rc<RefCell<Dir>>) -> usize {
+    root.borrow_mut().part_1_sizes()
 }
 
-pub fn part2(input: crate::Data) -> Result<(usize, Duration), Day7Error> {
-    let now = Instant::now();
-    let fs = Dir {
-        name: String::from("/"),
-        contents: Vec::new(),
-        obj: FSObjectType::Dir,
-        parent: None,
-        depth: 0,
-        size: None,
+fn part2(root: &Rc<RefCell<Dir>>) -> Result<usize, Error> {
+    let total_size: usize = 70_000_000;
+    let used_space = root.borrow_mut().get_size();
+    let unused = match total_size.checked_sub(used_space) {
+        Some(unused) => unused,
+        None => return Err(Error::solve(format!("{} used is more than the disk holds", used_space.separated_string()))),
     };
-    let root = Rc::new(RefCell::new(fs));
-    let mut current = Rc::clone(&root);
-    let mut cmd = Command::Noop;
+    let needed = 30_000_000;
+    if unused >= needed {
+        return Ok(0);
+    }
+    let to_free = needed - unused;
 
-    // Read in input
-    let lines = read_lines(get_filename("day7", input)).unwrap();
-    for line in lines {
-        if let Ok(line) = line {
-            if line.starts_with("$") {
-                cmd = parse_cmd(line);
-                match cmd {
-                    Command::Cd(ref dest) => {
-                        let new_dir = current.borrow().cd(dest);
-                        match new_dir {
-                            Err(new_dir) => {
-                                if current.borrow().name == "/" {
-                                    continue;
-                                } else {
-                                    return Err(new_dir);
-                                }
-                            }
-                            Ok(new_dir) => {
-                                current = new_dir;
-                            }
-                        }
-                    }
-                    _ => {}
-                }
-            } else {
-                match cmd {
-                    Command::Noop => { continue; }
-                    Command::Ls => {
-                        let mut words = line.split(" ");
-                        let file_type = words.next().unwrap();
-                        let name = words.next().unwrap();
-                        if file_type == "dir" {
-                            child_dir(Rc::clone(&current), name);
-                        } else {
-                            let size = file_type.parse::<usize>().unwrap();
-                            child_file(Rc::clone(&current), name, size);
-                        }
-                    }
-                    Command::Cd(_) => {
-                    }
-                }
-            }
-        }
+    trace!("filesystem:\n{}", root.borrow());
+    debug!("used {}/{}, need to free {}", used_space.separated_string(), total_size.separated_string(), to_free.separated_string());
+    let size_to_del = root.borrow_mut().delete_size(to_free, used_space, 0);
+
+    Ok(size_to_del)
+}
+
+examples! {
+    solution: Day7,
+    puzzle: {
+        input: "$ cd /
+$ ls
+dir a
+14848514 b.txt
+8504156 c.dat
+dir d
+$ cd a
+$ ls
+dir e
+29116 f
+2557 g
+62596 h.lst
+$ cd e
+$ ls
+584 i
+$ cd ..
+$ cd ..
+$ cd d
+$ ls
+4060174 j
+8033020 d.log
+5626152 d.ext
+7214296 k
+",
+        part1: "95437",
+        part2: "24933642",
+    },
+}
+
+impl Solution for Day7 {
+    const DAY: usize = 7;
+    const TITLE: &'static str = "No Space Left On Device";
+    const EXAMPLES: &'static [Example] = EXAMPLES;
+    type Parsed = Rc<RefCell<Dir>>;
+
+    fn parse(&self, input: &str) -> SolveResult<Self::Parsed> {
+        build_tree(&split_lines(input))
     }
 
-    let new_dir = current.borrow().cd("/")?;
-    current = new_dir;
+    fn part1(&self, root: &Self::Parsed) -> SolveResult<Answer> {
+        Ok(part1(root).into())
+    }
 
-    let total_size = 70000000;
-    let used_space = current.borrow_mut().get_size();
-    let unused = total_size - used_space;
-    let needed = 30_000_000;
-    let to_free = needed - unused;
+    fn part2(&self, root: &Self::Parsed) -> SolveResult<Answer> {
+        Ok(part2(root)?.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(input: &str) -> Vec<String> {
+        input.lines().map(String::from).collect()
+    }
+
+    #[test]
+    fn parse_cmd_errors() {
+        for line in ["$", "$ cd", "$ rm -rf /", "$ ls -la"] {
+            assert!(super::parse_cmd(line).is_err(), "'{}' should not parse", line);
+        }
+    }
 
-    if DEBUG {
-        println!("{}\n", current.borrow());
-        println!("Used {}/{}. To free: {}", used_space, total_size, to_free);
+    #[test]
+    fn bad_listing() {
+        let err = build_tree(&lines("$ cd /\n$ ls\ndir a\n12x b.txt")).unwrap_err();
+        match err {
+            Error::Parse(err) => {
+                assert_eq!(Some(4), err.line);
+                assert_eq!(1, err.column);
+            }
+            _ => panic!("expected a parse error, got {}", err),
+        }
     }
-    let size_to_del = current.borrow_mut().delete_size(to_free, used_space, 0);
 
-    Ok((size_to_del, now.elapsed()))
+    #[test]
+    fn missing_dir() {
+        let err = build_tree(&lines("$ cd /\n$ ls\ndir a\n$ cd a\n$ cd b")).unwrap_err();
+        match err {
+            Error::Parse(err) => {
+                assert_eq!(Some(5), err.line);
+                assert_eq!(6, err.column);
+            }
+            _ => panic!("expected a parse error, got {}", err),
+        }
+    }
+
+    #[test]
+    fn missing_dir_from_root() {
+        let err = build_tree(&lines("$ cd /\n$ cd missing\n$ ls\n100 a")).unwrap_err();
+        match err {
+            Error::Parse(err) => {
+                assert_eq!(Some(2), err.line);
+                assert_eq!(6, err.column);
+                assert!(err.message.contains("missing"), "{}", err.message);
+            }
+            _ => panic!("expected a parse error, got {}", err),
+        }
+        assert!(build_tree(&lines("$ cd /\n$ cd /\n$ ls\n100 a")).is_ok());
+    }
 }
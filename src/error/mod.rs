@@ -0,0 +1,163 @@
+use std::fmt;
+use std::error;
+use std::io;
+use std::str::FromStr;
+
+#[derive(Debug)]
+pub enum Error {
+    IO { path: String, source: io::Error },
+    Parse(ParseError),
+    Solve(String),
+    MissingFixture { day: usize, fixture: String, available: Vec<&'static str> },
+}
+
+// Where in the input a parse failed. Line and file are filled in on the way
+// up, by whoever knows them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub file: Option<String>,
+    pub line: Option<usize>,
+    pub column: usize,
+    pub text: String,
+    pub message: String,
+}
+
+impl Error {
+    // A parse error pointing at `fragment`, which should be a slice of `text`.
+    // If it isn't, the error points at the start of the line.
+    pub fn parse(text: &str, fragment: &str, message: impl Into<String>) -> Error {
+        let start = text.as_ptr() as usize;
+        let offset = (fragment.as_ptr() as usize).wrapping_sub(start);
+        let inside = offset.checked_add(fragment.len()).is_some_and(|end| end <= text.len());
+        let column = if inside && text.is_char_boundary(offset) {
+            text[..offset].chars().count() + 1
+        } else {
+            1
+        };
+        Error::Parse(ParseError {
+            file: None,
+            line: None,
+            column,
+            text: text.to_string(),
+            message: message.into(),
+        })
+    }
+
+    pub fn solve(message: impl Into<String>) -> Error {
+        Error::Solve(message.into())
+    }
+
+    // Line numbers are 1-based
+    pub fn at_line(mut self, line: usize) -> Error {
+        if let Error::Parse(ref mut err) = self {
+            err.line.get_or_insert(line);
+        }
+        self
+    }
+
+    pub fn in_file(mut self, file: &str) -> Error {
+        if let Error::Parse(ref mut err) = self {
+            err.file.get_or_insert_with(|| file.to_string());
+        }
+        self
+    }
+}
+
+// Parse `fragment` (a slice of `text`) as a number, reporting where it went wrong
+pub fn parse_number<T: FromStr>(text: &str, fragment: &str) -> Result<T, Error> {
+    fragment.trim().parse::<T>().map_err(|_| {
+        if fragment.is_empty() {
+            Error::parse(text, fragment, "expected a number")
+        } else {
+            Error::parse(text, fragment, format!("'{}' is not a valid number", fragment))
+        }
+    })
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let file = self.file.as_deref().unwrap_or("<input>");
+        let line = match self.line {
+            Some(line) => line.to_string(),
+            None => String::from("?"),
+        };
+        writeln!(f, "{}:{}:{}: {}", file, line, self.column, self.message)?;
+        let gutter = " ".repeat(line.len());
+        writeln!(f, "  {} | {}", line, self.text)?;
+        write!(f, "  {} | {}^", gutter, " ".repeat(self.column - 1))
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::IO { path, source } => write!(f, "could not read {}: {}", path, source),
+            Error::Parse(err) => write!(f, "{}", err),
+            Error::Solve(msg) => write!(f, "{}", msg),
+            Error::MissingFixture { day, fixture, available } => {
+                write!(f, "day {} has no {}", day, fixture)?;
+                if available.is_empty() {
+                    write!(f, ", and no inline examples")
+                } else {
+                    write!(f, " (inline examples: {})", available.join(", "))
+                }
+            }
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Error::IO { ref source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_column() {
+        let text = "2-x,6-8";
+        let err = Error::parse(text, &text[2..3], "bad").at_line(3).in_file("day4.txt");
+        let want = Error::Parse(ParseError {
+            file: Some(String::from("day4.txt")),
+            line: Some(3),
+            column: 3,
+            text: String::from(text),
+            message: String::from("bad"),
+        });
+        assert_eq!(want.to_string(), err.to_string());
+    }
+
+    #[test]
+    fn parse_unrelated_fragment() {
+        let err = Error::parse("abc", "xyz", "bad");
+        match err {
+            Error::Parse(err) => assert_eq!(1, err.column),
+            _ => panic!("expected a parse error"),
+        }
+    }
+
+    #[test]
+    fn display() {
+        let text = "move 1 from x to 2";
+        let err = Error::parse(text, &text[12..13], "bad stack").at_line(12).in_file("in.txt");
+        let want = "\
+in.txt:12:13: bad stack
+  12 | move 1 from x to 2
+     |             ^";
+        assert_eq!(want, err.to_string());
+    }
+
+    #[test]
+    fn number() {
+        let text = "12-ab";
+        assert_eq!(12, parse_number::<usize>(text, &text[0..2]).unwrap());
+        let err = parse_number::<usize>(text, &text[3..]).unwrap_err();
+        assert!(err.to_string().contains("'ab' is not a valid number"));
+    }
+}
@@ -0,0 +1,189 @@
+use std::fmt;
+use std::io::{self, LineWriter, Write};
+use std::sync::{Mutex, OnceLock};
+
+// Runtime tracing for the solutions. Messages are filtered per module
+// ("day5", "solution", ...) and go to stderr or a log file, never stdout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Off,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Level {
+    fn parse(name: &str) -> Option<Level> {
+        match name {
+            "off" => Some(Level::Off),
+            "info" => Some(Level::Info),
+            "debug" => Some(Level::Debug),
+            "trace" => Some(Level::Trace),
+            _ => None,
+        }
+    }
+
+    // Level for a number of -v flags
+    pub fn from_verbosity(count: usize) -> Level {
+        match count {
+            0 => Level::Off,
+            1 => Level::Info,
+            2 => Level::Debug,
+            _ => Level::Trace,
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Level::Off => "OFF",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// Which messages to show: a default level plus per-module overrides
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filter {
+    pub default: Level,
+    pub modules: Vec<(String, Level)>,
+}
+
+impl Default for Filter {
+    fn default() -> Filter {
+        Filter {
+            default: Level::Off,
+            modules: Vec::new(),
+        }
+    }
+}
+
+impl Filter {
+    // Parses specs like "debug", "day5=trace" or "info,day7=trace,day4=off"
+    pub fn parse(spec: &str) -> Result<Filter, String> {
+        let mut filter = Filter::default();
+        for item in spec.split(',').map(str::trim).filter(|item| !item.is_empty()) {
+            let (module, level) = match item.split_once('=') {
+                Some((module, level)) => (Some(module.trim()), level.trim()),
+                None => (None, item),
+            };
+            let level = match Level::parse(level) {
+                Some(level) => level,
+                None => return Err(format!("'{}' is not a log level (expected off, info, debug or trace)", level)),
+            };
+            match module {
+                Some(module) => filter.modules.push((module.to_string(), level)),
+                None => filter.default = level,
+            }
+        }
+        Ok(filter)
+    }
+
+    pub fn level(&self, module: &str) -> Level {
+        // Later entries win, so "day5=trace,day5=off" turns day5 off
+        match self.modules.iter().rev().find(|(name, _)| name == module) {
+            Some((_, level)) => *level,
+            None => self.default,
+        }
+    }
+}
+
+struct Logger {
+    filter: Filter,
+    out: Mutex<Box<dyn Write + Send>>,
+}
+
+static LOGGER: OnceLock<Logger> = OnceLock::new();
+
+// Logging stays off until this is called; only the first call has any effect
+pub fn init(filter: Filter, out: Box<dyn Write + Send>) {
+    let _ = LOGGER.set(Logger {
+        filter,
+        out: Mutex::new(Box::new(LineWriter::new(out))),
+    });
+}
+
+pub fn stderr() -> Box<dyn Write + Send> {
+    Box::new(io::stderr())
+}
+
+// "aoc2022::day7::tests" is logged as "day7"
+pub fn module(path: &str) -> &str {
+    path.split("::").nth(1).unwrap_or(path)
+}
+
+pub fn enabled(level: Level, module: &str) -> bool {
+    match LOGGER.get() {
+        Some(logger) => level != Level::Off && level <= logger.filter.level(module),
+        None => false,
+    }
+}
+
+pub fn write(level: Level, module: &str, args: fmt::Arguments) {
+    if let Some(logger) = LOGGER.get() {
+        if let Ok(mut out) = logger.out.lock() {
+            let _ = writeln!(out, "[{} {}] {}", level, module, args);
+        }
+    }
+}
+
+// `info!`, `debug!` and `trace!` take format arguments like `println!`, and
+// only format them when the calling module's level lets them through.
+macro_rules! log {
+    ($level:expr, $($arg:tt)+) => {{
+        let module = $crate::log::module(module_path!());
+        if $crate::log::enabled($level, module) {
+            $crate::log::write($level, module, format_args!($($arg)+));
+        }
+    }};
+}
+
+macro_rules! info {
+    ($($arg:tt)+) => { $crate::log::log!($crate::log::Level::Info, $($arg)+) };
+}
+
+macro_rules! debug {
+    ($($arg:tt)+) => { $crate::log::log!($crate::log::Level::Debug, $($arg)+) };
+}
+
+macro_rules! trace {
+    ($($arg:tt)+) => { $crate::log::log!($crate::log::Level::Trace, $($arg)+) };
+}
+
+pub(crate) use {debug, info, log, trace};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filter() {
+        let filter = Filter::parse("info, day7=trace,day4=off").unwrap();
+        assert_eq!(Level::Info, filter.level("day1"));
+        assert_eq!(Level::Trace, filter.level("day7"));
+        assert_eq!(Level::Off, filter.level("day4"));
+
+        let filter = Filter::parse("day5=debug,day5=off").unwrap();
+        assert_eq!(Level::Off, filter.level("day5"));
+        assert_eq!(Filter::default(), Filter::parse("").unwrap());
+    }
+
+    #[test]
+    fn filter_errors() {
+        for spec in ["loud", "day5=", "day5=everything"] {
+            assert!(Filter::parse(spec).is_err(), "{} should not parse", spec);
+        }
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!("day7", module("aoc2022::day7::tests"));
+        assert_eq!("aoc2022", module("aoc2022"));
+        assert_eq!(Level::Off, Level::from_verbosity(0));
+        assert_eq!(Level::Trace, Level::from_verbosity(5));
+    }
+}
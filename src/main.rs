@@ -1,3 +1,6 @@
+mod answers;
+mod bench;
+mod cli;
 mod day1;
 mod day2;
 mod day3;
@@ -5,64 +8,30 @@ mod day4;
 mod day5;
 mod day6;
 mod day7;
+mod error;
+mod log;
+mod solution;
 pub mod util;
 
+use std::path::PathBuf;
+
 #[cfg(test)]
 extern crate table_test;
-pub enum Data {
+
+// Where a day's puzzle data is read from
+#[derive(Debug, Clone, PartialEq)]
+pub enum Data<'a> {
+    // ./src/dayN/input.txt
     Input,
-    Test
+    // ./src/dayN/test.txt, or the day's first example if that doesn't exist
+    Test,
+    Path(PathBuf),
+    Stdin,
+    Str(&'a str),
+    // One of the day's inline examples, by name
+    Example(String),
 }
-fn main() {
-    println!("Hello, world!");
-    println!("Day 1:");
-    let (ans, dur)= day1::part1(Data::Input);
-    println!("  Part 1: {} in {:.2?}", ans, dur);
-    let (ans, dur) = day1::part2(Data::Input);
-    println!("  Part 2: {} in {:.2?}", ans, dur);
-
-    println!("Day 2:");
-    let (ans, dur) = day2::part1(Data::Input);
-    println!("  Part 1: {} in {:.2?}", ans, dur);
-    let (ans, dur) = day2::part2(Data::Input);
-    println!("  Part 2: {} in {:.2?}", ans, dur);
-
-    println!("Day 3:");
-    let (ans, dur) = day3::part1(Data::Input);
-    println!("  Part 1: {} in {:.2?}", ans, dur);
-    let (ans, dur) = day3::part2(Data::Input);
-    println!("  Part 2: {} in {:.2?}", ans, dur);
 
-    println!("Day 4:");
-    let (ans, dur) = day4::part1(Data::Input);
-    println!("  Part 1: {} in {:.2?}", ans, dur);
-    let (ans, dur) = day4::part2(Data::Input);
-    println!("  Part 2: {} in {:.2?}", ans, dur);
-
-    println!("Day 5:");
-    let (ans, dur) = day5::part1(Data::Input);
-    println!("  Part 1: {} in {:.2?}", ans, dur);
-    let (ans, dur) = day5::part2(Data::Input);
-    println!("  Part 2: {} in {:.2?}", ans, dur);
-
-    println!("Day 6:");
-    let res = day6::part1(Data::Input);
-    if let Ok(res) = res {
-        let (ans, dur) = res;
-        println!("  Part 1: {} in {:.2?}", ans, dur);
-    }
-    let res = day6::part2(Data::Input);
-    if let Ok((ans, dur)) = res {
-        println!("  Part 2: {} in {:.2?}", ans, dur);
-    }
-
-    println!("Day 7:");
-    let res = day7::part1(Data::Input);
-    if let Ok((ans, dur)) = res {
-        println!("  Part 1: {} in {:.2?}", ans, dur);
-    }
-    let res = day7::part2(Data::Input);
-    if let Ok((ans, dur)) = res {
-        println!("  Part 2: {} in {:.2?}", ans, dur);
-    }
+fn main() {
+    std::process::exit(cli::main());
 }
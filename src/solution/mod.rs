@@ -0,0 +1,323 @@
+use std::fmt;
+use std::io::BufRead;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::error::Error;
+use crate::log::info;
+use crate::util::{self, get_filename, read_to_string};
+use crate::{day1, day2, day3, day4, day5, day6, day7, Data};
+
+pub type SolveResult<T> = Result<T, Error>;
+
+// Answers come in a few shapes across the days; they're compared and
+// displayed uniformly by the runner.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Answer {
+    Int(isize),
+    UInt(usize),
+    Text(String),
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::Int(n) => write!(f, "{}", n),
+            Answer::UInt(n) => write!(f, "{}", n),
+            Answer::Text(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+impl From<isize> for Answer {
+    fn from(n: isize) -> Answer {
+        Answer::Int(n)
+    }
+}
+
+impl From<usize> for Answer {
+    fn from(n: usize) -> Answer {
+        Answer::UInt(n)
+    }
+}
+
+impl From<String> for Answer {
+    fn from(s: String) -> Answer {
+        Answer::Text(s)
+    }
+}
+
+// A worked example from a puzzle description, with the answers it should give
+#[derive(Debug)]
+pub struct Example {
+    pub name: &'static str,
+    pub input: &'static str,
+    pub part1: Option<&'static str>,
+    pub part2: Option<&'static str>,
+}
+
+// Declares a day's `EXAMPLES` and generates a test for each one:
+//
+//     examples! {
+//         solution: Day1,
+//         puzzle: {
+//             input: "1000\n2000",
+//             part1: "3000",
+//             part2: "3000",
+//         },
+//     }
+//
+// Either answer can be left out if it isn't known.
+macro_rules! examples {
+    (solution: $solution:ident, $($name:ident: {
+        input: $input:expr,
+        $(part1: $part1:expr,)?
+        $(part2: $part2:expr,)?
+    },)+) => {
+        const EXAMPLES: &[$crate::solution::Example] = &[$(
+            $crate::solution::Example {
+                name: stringify!($name),
+                input: $input,
+                part1: examples!(@answer $($part1)?),
+                part2: examples!(@answer $($part2)?),
+            },
+        )+];
+
+        #[cfg(test)]
+        mod example_tests {
+            $(
+                #[test]
+                fn $name() {
+                    $crate::solution::check_example(&super::$solution, stringify!($name));
+                }
+            )+
+        }
+    };
+    (@answer) => { None };
+    (@answer $answer:expr) => { Some($answer) };
+}
+pub(crate) use examples;
+
+// A day's puzzle: the runner reads the input, it's parsed once, then each
+// part solves from the parsed form.
+pub trait Solution {
+    const DAY: usize;
+    const TITLE: &'static str;
+    const EXAMPLES: &'static [Example] = &[];
+    type Parsed;
+
+    fn parse(&self, input: &str) -> SolveResult<Self::Parsed>;
+
+    // Days that can parse as they read set this and override
+    // `parse_reader`, so the runner streams their input rather than holding
+    // all of it in memory
+    const STREAMS: bool = false;
+
+    fn parse_reader(&self, reader: &mut dyn BufRead, file: &str) -> SolveResult<Self::Parsed> {
+        let mut text = String::new();
+        reader.read_to_string(&mut text).map_err(|source| Error::IO { path: file.to_string(), source })?;
+        self.parse(&text)
+    }
+
+    fn part1(&self, parsed: &Self::Parsed) -> SolveResult<Answer>;
+    fn part2(&self, parsed: &Self::Parsed) -> SolveResult<Answer>;
+}
+
+pub struct PartResult {
+    pub part: usize,
+    pub answer: SolveResult<Answer>,
+    pub time: Duration,
+}
+
+// Each phase is timed separately so it's clear where the time goes
+pub struct Run {
+    pub read_time: Duration,
+    pub parse_time: Duration,
+    pub parts: Vec<PartResult>,
+}
+
+// Object-safe view of a `Solution`, so days with different parsed types can
+// share a registry.
+pub trait Solver: Sync {
+    fn day(&self) -> usize;
+    fn title(&self) -> &'static str;
+    fn examples(&self) -> &'static [Example];
+    fn run(&self, input: &Data, parts: &[usize]) -> SolveResult<Run>;
+}
+
+// Examples live in the source rather than on disk, so one stands in for
+// `Data::Example`, and the first for `Data::Test` when there's no local
+// test.txt
+pub fn example_for(day: usize, examples: &'static [Example], input: &Data) -> Option<&'static Example> {
+    match input {
+        Data::Example(name) => examples.iter().find(|example| example.name == name),
+        Data::Test if !Path::new(&get_filename(&format!("day{}", day), input)).exists() => examples.first(),
+        _ => None,
+    }
+}
+
+// Swaps in the example standing in for `input`, if any. Also returns a name
+// for the data to use in error reports.
+fn resolve<'a>(day: usize, examples: &'static [Example], input: &Data<'a>) -> SolveResult<(Data<'a>, String)> {
+    if let Some(found) = example_for(day, examples, input) {
+        return Ok((Data::Str(found.input), format!("<day{} example {}>", day, found.name)));
+    }
+    let available = || examples.iter().map(|example| example.name).collect();
+    let file = get_filename(&format!("day{}", day), input);
+    match input {
+        Data::Example(name) => Err(Error::MissingFixture { day, fixture: format!("example '{}'", name), available: available() }),
+        Data::Test if !Path::new(&file).exists() => Err(Error::MissingFixture { day, fixture: file, available: available() }),
+        _ => Ok((input.clone(), file)),
+    }
+}
+
+// Reads a day's puzzle data, returning it along with the name to use for it
+// in error reports
+pub fn read(day: usize, examples: &'static [Example], input: &Data) -> SolveResult<(String, String)> {
+    let (input, file) = resolve(day, examples, input)?;
+    let text = read_to_string(&format!("day{}", day), &input)?;
+    info!("day {}: read {} bytes from {}", day, text.len(), file);
+    Ok((text, file))
+}
+
+// Opens a day's puzzle data for streaming, along with its name for error reports
+pub fn open<'a>(day: usize, examples: &'static [Example], input: &Data<'a>) -> SolveResult<(Box<dyn BufRead + 'a>, String)> {
+    let (input, file) = resolve(day, examples, input)?;
+    info!("day {}: streaming {}", day, file);
+    Ok((util::open(&format!("day{}", day), &input)?, file))
+}
+
+impl<S: Solution + Sync> Solver for S {
+    fn day(&self) -> usize {
+        S::DAY
+    }
+
+    fn title(&self) -> &'static str {
+        S::TITLE
+    }
+
+    fn examples(&self) -> &'static [Example] {
+        S::EXAMPLES
+    }
+
+    fn run(&self, input: &Data, parts: &[usize]) -> SolveResult<Run> {
+        let now = Instant::now();
+        let (parsed, file, read_time) = if S::STREAMS {
+            // Reading happens while parsing, so it all counts as parse time
+            let (mut reader, file) = open(S::DAY, S::EXAMPLES, input)?;
+            let parsed = self.parse_reader(&mut reader, &file).map_err(|err| err.in_file(&file))?;
+            (parsed, file, Duration::ZERO)
+        } else {
+            let (text, file) = read(S::DAY, S::EXAMPLES, input)?;
+            let read_time = now.elapsed();
+            let parsed = self.parse(&text).map_err(|err| err.in_file(&file))?;
+            (parsed, file, read_time)
+        };
+        let parse_time = now.elapsed() - read_time;
+
+        let mut results = Vec::new();
+        for &part in parts {
+            let now = Instant::now();
+            let answer = match part {
+                1 => self.part1(&parsed),
+                2 => self.part2(&parsed),
+                _ => Err(Error::solve(format!("day {} has no part {}", S::DAY, part))),
+            };
+            let answer = answer.map_err(|err| err.in_file(&file));
+            results.push(PartResult {
+                part,
+                answer,
+                time: now.elapsed(),
+            });
+        }
+        Ok(Run {
+            read_time,
+            parse_time,
+            parts: results,
+        })
+    }
+}
+
+static REGISTRY: &[&dyn Solver] = &[
+    &day1::Day1,
+    &day2::Day2,
+    &day3::Day3,
+    &day4::Day4,
+    &day5::Day5,
+    &day6::Day6,
+    &day7::Day7,
+];
+
+pub fn registry() -> &'static [&'static dyn Solver] {
+    REGISTRY
+}
+
+pub fn get(day: usize) -> Option<&'static dyn Solver> {
+    REGISTRY.iter().find(|solver| solver.day() == day).copied()
+}
+
+// Run one of a day's examples and check both answers; called by the tests
+// that `examples!` generates.
+#[cfg(test)]
+pub fn check_example<S: Solution>(solution: &S, name: &str) {
+    let example = match S::EXAMPLES.iter().find(|example| example.name == name) {
+        Some(example) => example,
+        None => panic!("day {} has no example '{}'", S::DAY, name),
+    };
+    let parsed = match solution.parse(example.input) {
+        Ok(parsed) => parsed,
+        Err(err) => panic!("day {} example '{}' failed to parse:\n{}", S::DAY, name, err),
+    };
+    for (part, want) in [(1, example.part1), (2, example.part2)] {
+        let want = match want {
+            Some(want) => want,
+            None => continue,
+        };
+        let res = match part {
+            1 => solution.part1(&parsed),
+            _ => solution.part2(&parsed),
+        };
+        match res {
+            Ok(res) => assert_eq!(want, res.to_string(), "day {} example '{}' part {}", S::DAY, name, part),
+            Err(err) => panic!("day {} example '{}' part {} failed:\n{}", S::DAY, name, part, err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registry_days() {
+        let days: Vec<usize> = registry().iter().map(|solver| solver.day()).collect();
+        assert_eq!(vec![1, 2, 3, 4, 5, 6, 7], days);
+    }
+
+    #[test]
+    fn get_day() {
+        assert_eq!(Some(3), get(3).map(|solver| solver.day()));
+        assert!(get(25).is_none());
+    }
+
+    #[test]
+    fn resolve_examples() {
+        let (input, file) = resolve(4, day4::Day4.examples(), &Data::Example(String::from("puzzle"))).unwrap();
+        assert_eq!(Data::Str(day4::Day4.examples()[0].input), input);
+        assert_eq!("<day4 example puzzle>", file);
+
+        let err = resolve(4, day4::Day4.examples(), &Data::Example(String::from("nope"))).unwrap_err();
+        assert!(err.to_string().contains("example 'nope'"));
+        assert!(err.to_string().contains("puzzle"));
+
+        let err = resolve(9, &[], &Data::Test).unwrap_err();
+        assert_eq!("day 9 has no ./src/day9/test.txt, and no inline examples", err.to_string());
+    }
+
+    #[test]
+    fn answer_display() {
+        assert_eq!("-4", Answer::from(-4isize).to_string());
+        assert_eq!("12", Answer::from(12usize).to_string());
+        assert_eq!("CMZ", Answer::from(String::from("CMZ")).to_string());
+    }
+}
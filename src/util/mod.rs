@@ -1,43 +1,113 @@
 use super::Data;
-use std::io::{BufRead, self};
-use std::path::Path;
+use crate::error::Error;
+use std::io::{BufRead, Read, self};
 use std::fs::File;
 
-pub fn get_filename(day: &str, input: Data) -> String {
-    let file: &str;
-    match input {
-        Data::Input => {file = "input.txt"}
-        Data::Test => file = "test.txt"
-    }
-    return format!("./src/{}/{}", day, file);
+// Where the puzzle data for `day` comes from, for messages and error reports
+pub fn get_filename(day: &str, input: &Data) -> String {
+    let file = match input {
+        Data::Input => "input.txt",
+        Data::Test => "test.txt",
+        Data::Path(path) => return path.display().to_string(),
+        Data::Stdin => return String::from("<stdin>"),
+        Data::Str(_) => return String::from("<string>"),
+        Data::Example(name) => return format!("<{} example {}>", day, name),
+    };
+    format!("./src/{}/{}", day, file)
+}
+
+pub fn open<'a>(day: &str, input: &Data<'a>) -> Result<Box<dyn BufRead + 'a>, Error> {
+    let reader: Box<dyn BufRead + 'a> = match input {
+        Data::Stdin => Box::new(io::stdin().lock()),
+        Data::Str(text) => Box::new(text.as_bytes()),
+        // Examples are compiled in; the solution runner swaps them for `Data::Str`
+        Data::Example(name) => return Err(Error::solve(format!("example '{}' can't be read as a file", name))),
+        _ => {
+            let path = get_filename(day, input);
+            match File::open(&path) {
+                Ok(file) => Box::new(io::BufReader::new(file)),
+                Err(source) => return Err(Error::IO { path, source }),
+            }
+        }
+    };
+    Ok(reader)
 }
 
-pub fn read_lines<P>(filename: P) -> io::Result<io::Lines<io::BufReader<File>>>
-where P: AsRef<Path>, {
-    let file = File::open(filename)?;
-    Ok(io::BufReader::new(file).lines())
+pub fn read_lines<R: BufRead>(reader: R) -> io::Lines<R> {
+    reader.lines()
+}
+
+pub fn split_lines(text: &str) -> Vec<String> {
+    text.lines().map(String::from).collect()
+}
+
+pub fn read_to_string(day: &str, input: &Data) -> Result<String, Error> {
+    let mut text = String::new();
+    open(day, input)?
+        .read_to_string(&mut text)
+        .map_err(|source| Error::IO { path: get_filename(day, input), source })?;
+    Ok(text)
+}
+
+// Parse each line with `f`, tagging any error with its (1-based) line number
+pub fn parse_lines<T, F>(lines: &[String], mut f: F) -> Result<Vec<T>, Error>
+where F: FnMut(&str) -> Result<T, Error>, {
+    lines
+        .iter()
+        .enumerate()
+        .map(|(idx, line)| f(line).map_err(|err| err.at_line(idx + 1)))
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::path::PathBuf;
     #[test]
     fn day1() {
-        let res = get_filename("day1", Data::Test);
+        let res = get_filename("day1", &Data::Test);
         let want =  "./src/day1/test.txt";
         assert_eq!(want, res);
-        let res = get_filename("day1", Data::Input);
+        let res = get_filename("day1", &Data::Input);
         let want = "./src/day1/input.txt";
         assert_eq!(want, res);
     }
 
     #[test]
     fn day2() {
-        let res = get_filename("day2", Data::Test);
+        let res = get_filename("day2", &Data::Test);
         let want = "./src/day2/test.txt";
         assert_eq!(want, res);
-        let res = get_filename("day2", Data::Input);
+        let res = get_filename("day2", &Data::Input);
         let want = "./src/day2/input.txt";
         assert_eq!(want, res);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn other_sources() {
+        let res = get_filename("day1", &Data::Path(PathBuf::from("/tmp/elves.txt")));
+        assert_eq!("/tmp/elves.txt", res);
+        assert_eq!("<stdin>", get_filename("day1", &Data::Stdin));
+        assert_eq!("<string>", get_filename("day1", &Data::Str("1\n2")));
+    }
+
+    #[test]
+    fn read_str() {
+        let res = split_lines(&read_to_string("day1", &Data::Str("1000\n\n2000\n")).unwrap());
+        assert_eq!(vec!["1000", "", "2000"], res);
+        let res = read_to_string("day6", &Data::Str("abcd\n")).unwrap();
+        assert_eq!("abcd\n", res);
+    }
+
+    #[test]
+    fn read_path() {
+        let res = read_to_string("", &Data::Path(PathBuf::from("./Cargo.toml"))).unwrap();
+        assert_eq!(Some("[package]"), res.lines().next());
+    }
+
+    #[test]
+    fn missing_file() {
+        let err = read_to_string("", &Data::Path(PathBuf::from("./no/such/file.txt"))).unwrap_err();
+        assert!(err.to_string().starts_with("could not read ./no/such/file.txt"));
+    }
+}